@@ -0,0 +1,427 @@
+use std::fmt::Display;
+
+use crate::error::LexerError;
+use crate::lexer::Lexer;
+use crate::position::{Location, Span};
+use crate::token::{Token, Type};
+use crate::token_stream::TokenStream;
+
+// Trivia
+//
+// Source text between tokens which doesn't change the meaning of a file.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Trivia {
+    Whitespace(String),
+    // Either `\n` or `\r\n`
+    Newline(String),
+    LineComment(String),
+    BlockComment(String),
+}
+
+impl Trivia {
+    pub fn is_comment(&self) -> bool {
+        matches!(self, Self::LineComment(_) | Self::BlockComment(_))
+    }
+
+    // Splits whitespace into runs of blanks and single newlines.
+    fn whitespace(text: &str, trivia: &mut Vec<Trivia>) {
+        let mut rest = text;
+
+        while !rest.is_empty() {
+            let (piece, tail) = match rest.find('\n') {
+                Some(0) => rest.split_at(1),
+                Some(1) if rest.starts_with('\r') => rest.split_at(2),
+                Some(i) if rest[..i].ends_with('\r') => rest.split_at(i - 1),
+                Some(i) => rest.split_at(i),
+                None => rest.split_at(rest.len()),
+            };
+
+            match piece.ends_with('\n') {
+                true => trivia.push(Self::Newline(piece.to_string())),
+                false => trivia.push(Self::Whitespace(piece.to_string())),
+            }
+            rest = tail;
+        }
+    }
+}
+
+impl Display for Trivia {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Whitespace(v) | Self::Newline(v) => write!(f, "{v}"),
+            Self::LineComment(v) => write!(f, "//{v}"),
+            Self::BlockComment(v) => write!(f, "/*{v}*/"),
+        }
+    }
+}
+
+// Syntax token
+//
+// Token together with its exact spelling. Trailing trivia runs until the end of the line, the
+// newline and everything after it leads the next token.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxToken {
+    token: Token,
+    text: String,
+    leading: Vec<Trivia>,
+    trailing: Vec<Trivia>,
+}
+
+impl SyntaxToken {
+    pub fn new(token: Token, text: String) -> Self {
+        Self {
+            token,
+            text,
+            leading: vec![],
+            trailing: vec![],
+        }
+    }
+
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn span(&self) -> Span {
+        self.token.span()
+    }
+
+    pub fn leading(&self) -> &[Trivia] {
+        &self.leading
+    }
+
+    pub fn trailing(&self) -> &[Trivia] {
+        &self.trailing
+    }
+}
+
+impl Display for SyntaxToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for trivia in &self.leading {
+            write!(f, "{trivia}")?;
+        }
+
+        write!(f, "{}", self.text)?;
+
+        for trivia in &self.trailing {
+            write!(f, "{trivia}")?;
+        }
+
+        Ok(())
+    }
+}
+
+// Lossless token layer
+//
+// Flat list of the tokens of a file with their trivia, printing it reproduces the source byte for
+// byte. The typed AST is a view over it: every node keeps the span of its tokens, `text` gives back
+// the exact source of a node and `replace` rewrites nodes while keeping the rest of the file as it
+// was written.
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cst {
+    tokens: Vec<SyntaxToken>,
+    // Trivia after the last token
+    eof: Vec<Trivia>,
+}
+
+impl Cst {
+    pub fn tokens(&self) -> &[SyntaxToken] {
+        &self.tokens
+    }
+
+    pub fn eof(&self) -> &[Trivia] {
+        &self.eof
+    }
+
+    // Tokens overlapping `span`, the trivia around them included.
+    pub fn tokens_in(&self, span: Span) -> &[SyntaxToken] {
+        let start = span.start().offset();
+        let end = span.end().offset();
+
+        let first = self
+            .tokens
+            .partition_point(|t| t.span().end().offset() <= start);
+        let last = self
+            .tokens
+            .partition_point(|t| t.span().start().offset() < end);
+
+        &self.tokens[first..last.max(first)]
+    }
+
+    // Exact source text of `span` e.g. a message including the comments inside of it.
+    pub fn text(&self, span: Span) -> String {
+        let tokens = self.tokens_in(span);
+        let mut text = String::new();
+
+        for (i, token) in tokens.iter().enumerate() {
+            if i > 0 {
+                token
+                    .leading
+                    .iter()
+                    .for_each(|t| text.push_str(&t.to_string()));
+            }
+
+            text.push_str(&token.text);
+
+            if i + 1 < tokens.len() {
+                token
+                    .trailing
+                    .iter()
+                    .for_each(|t| text.push_str(&t.to_string()));
+            }
+        }
+
+        text
+    }
+
+    // Source with the text of each span replaced e.g. by a reprinted node, everything outside of
+    // the spans is kept byte for byte. `None` when spans overlap or aren't in the source.
+    pub fn replace(&self, edits: &[(Span, String)]) -> Option<String> {
+        let source = self.to_string();
+        let mut edits: Vec<&(Span, String)> = edits.iter().collect();
+        edits.sort_by_key(|(span, _)| span.start().offset());
+
+        let mut res = String::new();
+        let mut offset = 0;
+
+        for (span, text) in edits {
+            res.push_str(source.get(offset..span.start().offset())?);
+            res.push_str(text);
+            offset = span.end().offset();
+        }
+        res.push_str(source.get(offset..)?);
+
+        Some(res)
+    }
+
+    // Tokens for the parser, comments are kept since the AST holds them as members.
+    pub fn token_stream(&self) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        let mut loc = Location::default();
+
+        for token in &self.tokens {
+            Self::push_comments(&mut tokens, &mut loc, &token.leading);
+            tokens.push_concat(token.token.clone());
+            loc = token.span().end();
+            Self::push_comments(&mut tokens, &mut loc, &token.trailing);
+        }
+        Self::push_comments(&mut tokens, &mut loc, &self.eof);
+
+        tokens
+    }
+
+    // Comments are pushed as tokens, `loc` is moved past every trivia to recover their spans.
+    fn push_comments(tokens: &mut TokenStream, loc: &mut Location, trivia: &[Trivia]) {
+        for trivia in trivia {
+            let start = *loc;
+            trivia.to_string().bytes().for_each(|b| loc.advance(b));
+
+            let typ = match trivia {
+                Trivia::LineComment(v) => Type::LineComment(v.clone()),
+                Trivia::BlockComment(v) => Type::BlockComment(v.clone()),
+                _ => continue,
+            };
+            tokens.push(Token::new(typ, Span::new(start, *loc)));
+        }
+    }
+
+    // Splits the pending trivia, the part before the first newline trails the previous token and
+    // the rest is returned to lead the next one.
+    fn attach(&mut self, pending: &mut Vec<Trivia>) -> Vec<Trivia> {
+        let split = match self.tokens.last() {
+            Some(_) => pending
+                .iter()
+                .position(|t| matches!(t, Trivia::Newline(_)))
+                .unwrap_or(pending.len()),
+            None => 0,
+        };
+
+        let leading = pending.split_off(split);
+        if let Some(last) = self.tokens.last_mut() {
+            last.trailing.append(pending);
+        }
+
+        leading
+    }
+}
+
+impl TryFrom<&str> for Cst {
+    type Error = LexerError;
+
+    fn try_from(source: &str) -> Result<Self, Self::Error> {
+        let mut cst = Cst::default();
+
+        // Trivia seen since the last token, moved to the trailing trivia of that token once a
+        // newline shows up or the next token starts.
+        let mut pending: Vec<Trivia> = vec![];
+        let mut offset = 0;
+
+        for token in Lexer::new(source.as_bytes()).tokens()? {
+            let span = token.span();
+            Trivia::whitespace(&source[offset..span.start().offset()], &mut pending);
+            offset = span.end().offset();
+
+            let text = &source[span.start().offset()..offset];
+            match token.typ() {
+                Type::LineComment(v) => pending.push(Trivia::LineComment(v.clone())),
+                Type::BlockComment(v) => pending.push(Trivia::BlockComment(v.clone())),
+                _ => {
+                    let mut next = SyntaxToken::new(token.clone(), text.to_string());
+                    next.leading = cst.attach(&mut pending);
+                    cst.tokens.push(next);
+                }
+            }
+        }
+
+        Trivia::whitespace(&source[offset..], &mut pending);
+        cst.eof = cst.attach(&mut pending);
+
+        Ok(cst)
+    }
+}
+
+impl TryFrom<&[u8]> for Cst {
+    type Error = LexerError;
+
+    fn try_from(source: &[u8]) -> Result<Self, Self::Error> {
+        let source = std::str::from_utf8(source).map_err(|e| {
+            // Points at the first invalid byte
+            let mut loc = Location::default();
+            source[..e.valid_up_to()]
+                .iter()
+                .for_each(|b| loc.advance(*b));
+
+            LexerError::InvalidEncoding {
+                reason: e.to_string(),
+                span: Span::new(loc, loc),
+            }
+        })?;
+
+        Cst::try_from(source)
+    }
+}
+
+impl Display for Cst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for token in &self.tokens {
+            write!(f, "{token}")?;
+        }
+
+        for trivia in &self.eof {
+            write!(f, "{trivia}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lossless_ok() {
+        let sources = [
+            include_str!("../example.proto"),
+            "",
+            "  // only a comment\r\n",
+            "message A { string a = 1;  // trailing\r\n\t/* lead */ int32 b = 2; }\n\n",
+            "option s = \"a\" \n 'b';",
+        ];
+
+        for source in sources {
+            let cst = Cst::try_from(source).unwrap();
+            assert_eq!(cst.to_string(), source);
+        }
+    }
+
+    #[test]
+    fn trivia_ok() {
+        let cst = Cst::try_from("a; // x\n  /* y */ b /* z */\n").unwrap();
+        let tokens = cst.tokens();
+
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(
+            tokens[1].trailing(),
+            [
+                Trivia::Whitespace(" ".to_string()),
+                Trivia::LineComment(" x".to_string()),
+            ]
+        );
+        assert_eq!(
+            tokens[2].leading(),
+            [
+                Trivia::Newline("\n".to_string()),
+                Trivia::Whitespace("  ".to_string()),
+                Trivia::BlockComment(" y ".to_string()),
+                Trivia::Whitespace(" ".to_string()),
+            ]
+        );
+        assert_eq!(tokens[2].trailing().len(), 2);
+        assert_eq!(cst.eof(), [Trivia::Newline("\n".to_string())]);
+    }
+
+    #[test]
+    fn token_stream_ok() {
+        let cst = Cst::try_from("a = \"b\"\n 'c'; // d").unwrap();
+        let types: Vec<Type> = cst.token_stream().iter().map(|t| t.typ().clone()).collect();
+
+        assert_eq!(types.len(), 5);
+        assert_eq!(types[4], Type::LineComment(" d".to_string()));
+        match &types[2] {
+            Type::StringLit(v) => assert_eq!(v.as_str(), Some("bc")),
+            invalid => panic!("expected string literal, got {invalid}"),
+        }
+    }
+
+    #[test]
+    fn text_ok() {
+        let source = "message A {\n  // a\n  string a = 1; // b\n}\n";
+        let cst = Cst::try_from(source).unwrap();
+
+        let field = &cst.tokens()[3..8];
+        let span = field[0].span().join(&field[4].span());
+        assert_eq!(cst.text(span), "string a = 1;");
+
+        let message = cst.tokens()[0].span().join(&cst.tokens()[8].span());
+        assert_eq!(cst.text(message), source.trim_end());
+    }
+
+    #[test]
+    fn replace_ok() {
+        let source = "message A {\n  // a\n  string  a = 1; // b\n  int32 c = 2;\n}\n";
+        let cst = Cst::try_from(source).unwrap();
+
+        let a = cst.tokens()[3].span().join(&cst.tokens()[7].span());
+        let c = cst.tokens()[8].span().join(&cst.tokens()[12].span());
+        let edits = [
+            (c, "int64 c = 2;".to_string()),
+            (a, "bytes a = 1;".to_string()),
+        ];
+        assert_eq!(
+            cst.replace(&edits).unwrap(),
+            "message A {\n  // a\n  bytes a = 1; // b\n  int64 c = 2;\n}\n"
+        );
+        assert_eq!(cst.replace(&[]).unwrap(), source);
+
+        let message = cst.tokens()[0].span().join(&cst.tokens()[13].span());
+        assert_eq!(
+            cst.replace(&[(message, String::new()), (a, String::new())]),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_encoding_err() {
+        let error = Cst::try_from(&b"message A {}\n\xff"[..]).unwrap_err();
+
+        assert_eq!(error.code(), "E0002");
+        assert_eq!(error.span().map(|s| s.start().line()), Some(2));
+    }
+}
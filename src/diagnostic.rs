@@ -0,0 +1,39 @@
+use std::fmt::Display;
+
+use crate::error::ParserError;
+use crate::position::Span;
+
+// Diagnostic
+//
+// Problem found while parsing, the parser records it and carries on with the next statement so a
+// file reports all of its errors at once.
+
+#[derive(Debug)]
+pub struct Diagnostic {
+    error: ParserError,
+    span: Span,
+}
+
+impl Diagnostic {
+    pub fn new(error: ParserError, span: Span) -> Self {
+        Self { error, span }
+    }
+
+    pub fn error(&self) -> &ParserError {
+        &self.error
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn into_error(self) -> ParserError {
+        self.error
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.span.start(), self.error)
+    }
+}
@@ -1,11 +1,25 @@
 use thiserror::Error;
 
+use crate::position::Span;
+use crate::token::Token;
+
+// Error codes
+//
+// Stable codes for every kind of error, tools should match on these rather than on messages.
+
+pub const E_IO: &str = "E0001";
+pub const E_INVALID_ENCODING: &str = "E0002";
+pub const E_INVALID_TOKEN: &str = "E0003";
+pub const E_INVALID_LITERAL: &str = "E0004";
+pub const E_UNEXPECTED_TOKEN: &str = "E0005";
+pub const E_UNEXPECTED_EOF: &str = "E0006";
+pub const E_DUPLICATE_DEFINITION: &str = "E0007";
+pub const E_IMPORT_NOT_FOUND: &str = "E0008";
+pub const E_IMPORT_CYCLE: &str = "E0009";
+pub const E_INVALID_PATH: &str = "E0010";
+
 #[derive(Debug, Error)]
 pub enum BufferError {
-    #[error("convert byte {0} to character")]
-    Convert(u8),
-    #[error("end of file")]
-    EOF,
     #[error(transparent)]
     IO(#[from] std::io::Error),
 }
@@ -14,20 +28,150 @@ pub enum BufferError {
 pub enum LexerError {
     #[error("buffer error: {0}")]
     Buffer(#[from] BufferError),
-    #[error("invalid token: '{0}'")]
-    Invalid(String),
-    #[error(transparent)]
-    Regex(#[from] regex::Error),
-    #[error("{0}")]
-    Generic(String), // Rename to Tokenize
+    #[error("source is not valid UTF-8, {reason}")]
+    InvalidEncoding { reason: String, span: Span },
+    #[error("invalid token '{lexeme}', {reason}")]
+    InvalidToken {
+        lexeme: String,
+        reason: String,
+        span: Span,
+    },
+    #[error("invalid literal {literal}, {reason}")]
+    InvalidLiteral {
+        literal: String,
+        reason: String,
+        // `None` until the caller holding the literal sets it
+        span: Option<Span>,
+    },
+}
+
+impl LexerError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Buffer(_) => E_IO,
+            Self::InvalidEncoding { .. } => E_INVALID_ENCODING,
+            Self::InvalidToken { .. } => E_INVALID_TOKEN,
+            Self::InvalidLiteral { .. } => E_INVALID_LITERAL,
+        }
+    }
+
+    // Location of the error, `None` when it isn't tied to the source e.g. a failed read.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Buffer(_) => None,
+            Self::InvalidEncoding { span, .. } | Self::InvalidToken { span, .. } => Some(*span),
+            Self::InvalidLiteral { span, .. } => *span,
+        }
+    }
+
+    // Sets the span of an error raised without knowing where e.g. while decoding a literal.
+    pub fn with_span(mut self, at: Span) -> Self {
+        if let Self::InvalidLiteral { span, .. } = &mut self {
+            span.get_or_insert(at);
+        }
+
+        self
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum ParserError {
     #[error("lexer error: {0}")]
     Lexer(#[from] LexerError),
-    #[error("syntax error: expected '{0}', got '{1}'")]
-    Syntax(String, String),
+    #[error("expected {expected}, found '{found}'")]
+    UnexpectedToken {
+        expected: String,
+        found: String,
+        // `None` until the caller holding the token sets it
+        span: Option<Span>,
+    },
+    #[error("expected {expected}, found end of file")]
+    UnexpectedEof { expected: String, span: Span },
+    #[error("invalid value '{found}', expected {expected}")]
+    InvalidLiteral {
+        expected: String,
+        found: String,
+        // `None` until the caller holding the value sets it
+        span: Option<Span>,
+    },
+    #[error("'{name}' is defined more than once")]
+    DuplicateDefinition {
+        name: String,
+        span: Span,
+        // Span of the first definition
+        previous: Span,
+    },
+}
+
+impl ParserError {
+    // Token not matching what the grammar expects at its position.
+    pub fn unexpected(expected: impl Into<String>, token: &Token) -> Self {
+        Self::UnexpectedToken {
+            expected: expected.into(),
+            found: format!("{}", token.typ()),
+            span: Some(token.span()),
+        }
+    }
+
+    // Text of a token which isn't valid where it's used e.g. a non-scalar map key type. The span is
+    // set by the caller holding the token.
+    pub fn expected(expected: impl Into<String>, found: impl Into<String>) -> Self {
+        Self::UnexpectedToken {
+            expected: expected.into(),
+            found: found.into(),
+            span: None,
+        }
+    }
+
+    pub fn eof(expected: impl Into<String>, span: Span) -> Self {
+        Self::UnexpectedEof {
+            expected: expected.into(),
+            span,
+        }
+    }
+
+    // Well formed value outside of what's allowed e.g. field number 0.
+    pub fn invalid(expected: impl Into<String>, found: impl Into<String>) -> Self {
+        Self::InvalidLiteral {
+            expected: expected.into(),
+            found: found.into(),
+            span: None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Lexer(e) => e.code(),
+            Self::UnexpectedToken { .. } => E_UNEXPECTED_TOKEN,
+            Self::UnexpectedEof { .. } => E_UNEXPECTED_EOF,
+            Self::InvalidLiteral { .. } => E_INVALID_LITERAL,
+            Self::DuplicateDefinition { .. } => E_DUPLICATE_DEFINITION,
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Lexer(e) => e.span(),
+            Self::UnexpectedToken { span, .. } | Self::InvalidLiteral { span, .. } => *span,
+            Self::UnexpectedEof { span, .. } | Self::DuplicateDefinition { span, .. } => {
+                Some(*span)
+            }
+        }
+    }
+
+    // Sets the span of an error raised without knowing where, spans already set are kept.
+    pub fn with_span(mut self, at: Span) -> Self {
+        match &mut self {
+            Self::Lexer(LexerError::InvalidLiteral { span, .. })
+            | Self::UnexpectedToken { span, .. }
+            | Self::InvalidLiteral { span, .. } => {
+                span.get_or_insert(at);
+            }
+            _ => (),
+        }
+
+        self
+    }
 }
 
 #[derive(Debug, Error)]
@@ -38,5 +182,85 @@ pub enum ProtobufError {
     Lexer(#[from] LexerError),
     #[error(transparent)]
     Parser(#[from] ParserError),
+    // Error in a file loaded by name, `text` is its source for rendering the error
+    #[error("{name}: {error}")]
+    File {
+        name: String,
+        text: String,
+        error: Box<ProtobufError>,
+    },
+    #[error(
+        "import '{path}' not found in any include root{}",
+        .importer.as_ref().map(|v| format!(", imported by '{v}'")).unwrap_or_default()
+    )]
+    ImportNotFound {
+        path: String,
+        // File containing the import, `None` for the files loaded directly
+        importer: Option<String>,
+    },
+    #[error("import cycle {}", .cycle.join(" -> "))]
+    ImportCycle { cycle: Vec<String> },
+    #[error("import path '{path}' goes above the include root")]
+    InvalidPath { path: String },
+}
+
+impl ProtobufError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::IO(_) => E_IO,
+            Self::Lexer(e) => e.code(),
+            Self::Parser(e) => e.code(),
+            Self::File { error, .. } => error.code(),
+            Self::ImportNotFound { .. } => E_IMPORT_NOT_FOUND,
+            Self::ImportCycle { .. } => E_IMPORT_CYCLE,
+            Self::InvalidPath { .. } => E_INVALID_PATH,
+        }
+    }
+
+    // Location of the error in its file, `None` when it isn't tied to the source.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Lexer(e) => e.span(),
+            Self::Parser(e) => e.span(),
+            Self::File { error, .. } => error.span(),
+            _ => None,
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::position::Location;
+    use crate::token::Type;
+
+    use super::*;
+
+    #[test]
+    fn code_ok() {
+        let span = Span::new(Location::new(4, 1, 5), Location::new(7, 1, 8));
+        let token = Token::new(Type::Ident("foo".to_string()), span);
+
+        let unexpected = ParserError::unexpected("field index", &token);
+        assert_eq!(unexpected.code(), "E0005");
+        assert_eq!(unexpected.span(), Some(span));
+        assert_eq!(format!("{unexpected}"), "expected field index, found 'foo'");
+
+        let invalid = ParserError::invalid("field number in range 1 to 10", "0");
+        assert_eq!(invalid.span(), None);
+        let invalid = invalid.with_span(span).with_span(Span::default());
+        assert_eq!(invalid.span(), Some(span));
+        assert_eq!(invalid.code(), "E0004");
+
+        let lexer = ParserError::from(LexerError::InvalidLiteral {
+            literal: "\"foo".to_string(),
+            reason: "unterminated string literal".to_string(),
+            span: None,
+        });
+        assert_eq!(lexer.code(), invalid.code());
+        assert_eq!(lexer.with_span(span).span(), Some(span));
+
+        let io = ProtobufError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(io.code(), "E0001");
+        assert_eq!(ParserError::eof("';'", span).code(), "E0006");
+    }
+}
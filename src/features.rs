@@ -0,0 +1,939 @@
+// Feature resolution for protobuf editions.
+//
+// Every element inherits the features of its parent (file -> message -> field, file -> enum -> enum
+// value, file -> service -> rpc) and `features.*` options override the inherited values. Files
+// using `syntax` are resolved with the defaults of the matching legacy edition, including the
+// implied features of `required`, proto3 `optional`, `packed` and groups.
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::error::ParserError;
+use crate::token::Constant;
+use crate::types::enumerate::{Enum, EnumMember};
+use crate::types::extend::{Extend, ExtendMember};
+use crate::types::field::{Field, Label};
+use crate::types::message::{Message, MessageMember};
+use crate::types::oneof::{Oneof, OneofMember};
+use crate::types::option_field::OptionField;
+use crate::types::option_name::{OptionName, OptionNamePart};
+use crate::types::option_value::OptionValue;
+use crate::types::proto::{Proto, ProtoMember};
+use crate::types::service::{Service, ServiceMember};
+use crate::types::syntax::Syntax;
+
+const FEATURES_URL: &str = "https://protobuf.dev/editions/features/";
+const FEATURES: &str = "features";
+
+fn invalid_value(feature: &str, value: &str) -> ParserError {
+    ParserError::invalid(
+        format!("valid value for {feature}, check {FEATURES_URL} for more info"),
+        value,
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldPresence {
+    Explicit,
+    Implicit,
+    LegacyRequired,
+}
+
+impl TryFrom<&str> for FieldPresence {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "EXPLICIT" => Ok(Self::Explicit),
+            "IMPLICIT" => Ok(Self::Implicit),
+            "LEGACY_REQUIRED" => Ok(Self::LegacyRequired),
+            invalid => Err(invalid_value("field_presence", invalid)),
+        }
+    }
+}
+
+impl Display for FieldPresence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Explicit => "EXPLICIT",
+            Self::Implicit => "IMPLICIT",
+            Self::LegacyRequired => "LEGACY_REQUIRED",
+        };
+        write!(f, "{value}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnumType {
+    Open,
+    Closed,
+}
+
+impl TryFrom<&str> for EnumType {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "OPEN" => Ok(Self::Open),
+            "CLOSED" => Ok(Self::Closed),
+            invalid => Err(invalid_value("enum_type", invalid)),
+        }
+    }
+}
+
+impl Display for EnumType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Open => "OPEN",
+            Self::Closed => "CLOSED",
+        };
+        write!(f, "{value}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RepeatedFieldEncoding {
+    Packed,
+    Expanded,
+}
+
+impl TryFrom<&str> for RepeatedFieldEncoding {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "PACKED" => Ok(Self::Packed),
+            "EXPANDED" => Ok(Self::Expanded),
+            invalid => Err(invalid_value("repeated_field_encoding", invalid)),
+        }
+    }
+}
+
+impl Display for RepeatedFieldEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Packed => "PACKED",
+            Self::Expanded => "EXPANDED",
+        };
+        write!(f, "{value}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Utf8Validation {
+    Verify,
+    None,
+}
+
+impl TryFrom<&str> for Utf8Validation {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "VERIFY" => Ok(Self::Verify),
+            "NONE" => Ok(Self::None),
+            invalid => Err(invalid_value("utf8_validation", invalid)),
+        }
+    }
+}
+
+impl Display for Utf8Validation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Verify => "VERIFY",
+            Self::None => "NONE",
+        };
+        write!(f, "{value}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MessageEncoding {
+    LengthPrefixed,
+    Delimited,
+}
+
+impl TryFrom<&str> for MessageEncoding {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "LENGTH_PREFIXED" => Ok(Self::LengthPrefixed),
+            "DELIMITED" => Ok(Self::Delimited),
+            invalid => Err(invalid_value("message_encoding", invalid)),
+        }
+    }
+}
+
+impl Display for MessageEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::LengthPrefixed => "LENGTH_PREFIXED",
+            Self::Delimited => "DELIMITED",
+        };
+        write!(f, "{value}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JsonFormat {
+    Allow,
+    LegacyBestEffort,
+}
+
+impl TryFrom<&str> for JsonFormat {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "ALLOW" => Ok(Self::Allow),
+            "LEGACY_BEST_EFFORT" => Ok(Self::LegacyBestEffort),
+            invalid => Err(invalid_value("json_format", invalid)),
+        }
+    }
+}
+
+impl Display for JsonFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = match self {
+            Self::Allow => "ALLOW",
+            Self::LegacyBestEffort => "LEGACY_BEST_EFFORT",
+        };
+        write!(f, "{value}")
+    }
+}
+
+// FeatureSet
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeatureSet {
+    pub field_presence: FieldPresence,
+    pub enum_type: EnumType,
+    pub repeated_field_encoding: RepeatedFieldEncoding,
+    pub utf8_validation: Utf8Validation,
+    pub message_encoding: MessageEncoding,
+    pub json_format: JsonFormat,
+}
+
+impl FeatureSet {
+    pub fn proto2() -> Self {
+        Self {
+            field_presence: FieldPresence::Explicit,
+            enum_type: EnumType::Closed,
+            repeated_field_encoding: RepeatedFieldEncoding::Expanded,
+            utf8_validation: Utf8Validation::None,
+            message_encoding: MessageEncoding::LengthPrefixed,
+            json_format: JsonFormat::LegacyBestEffort,
+        }
+    }
+
+    pub fn proto3() -> Self {
+        Self {
+            field_presence: FieldPresence::Implicit,
+            enum_type: EnumType::Open,
+            repeated_field_encoding: RepeatedFieldEncoding::Packed,
+            utf8_validation: Utf8Validation::Verify,
+            message_encoding: MessageEncoding::LengthPrefixed,
+            json_format: JsonFormat::Allow,
+        }
+    }
+
+    // Defaults of edition 2023
+    pub fn edition2023() -> Self {
+        Self {
+            field_presence: FieldPresence::Explicit,
+            ..Self::proto3()
+        }
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.field_presence == FieldPresence::LegacyRequired
+    }
+
+    pub fn is_open_enum(&self) -> bool {
+        self.enum_type == EnumType::Open
+    }
+
+    pub fn is_delimited(&self) -> bool {
+        self.message_encoding == MessageEncoding::Delimited
+    }
+
+    pub fn validates_utf8(&self) -> bool {
+        self.utf8_validation == Utf8Validation::Verify
+    }
+
+    // Sets a single feature e.g. `field_presence` to `IMPLICIT`.
+    pub fn set(&mut self, feature: &str, value: &OptionValue) -> Result<(), ParserError> {
+        let value = match value.as_constant().and_then(|v| v.as_ident()) {
+            Some(v) => v,
+            None => return Err(invalid_value(feature, &format!("{value}"))),
+        };
+
+        match feature {
+            "field_presence" => self.field_presence = FieldPresence::try_from(value)?,
+            "enum_type" => self.enum_type = EnumType::try_from(value)?,
+            "repeated_field_encoding" => {
+                self.repeated_field_encoding = RepeatedFieldEncoding::try_from(value)?
+            }
+            "utf8_validation" => self.utf8_validation = Utf8Validation::try_from(value)?,
+            "message_encoding" => self.message_encoding = MessageEncoding::try_from(value)?,
+            "json_format" => self.json_format = JsonFormat::try_from(value)?,
+            invalid => {
+                return Err(ParserError::invalid(
+                    format!("feature, check {FEATURES_URL} for more info"),
+                    invalid,
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns a copy of the set with the `features.*` options applied, other options are ignored.
+    // Features can also be set all at once e.g. `features = { field_presence: IMPLICIT }`.
+    pub fn with_options<'a, I>(&self, options: I) -> Result<Self, ParserError>
+    where
+        I: IntoIterator<Item = (&'a OptionName, &'a OptionValue)>,
+    {
+        let mut res = *self;
+
+        for (name, value) in options {
+            match name.parts.as_slice() {
+                [OptionNamePart::Simple(v), OptionNamePart::Simple(feature)] if v == FEATURES => {
+                    res.set(feature, value)?;
+                }
+                [OptionNamePart::Simple(v)] if v == FEATURES => {
+                    let message = match value.as_message() {
+                        Some(v) => v,
+                        None => return Err(invalid_value(FEATURES, &format!("{value}"))),
+                    };
+
+                    for (feature, value) in &message.fields {
+                        res.set(feature, value)?;
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+impl Default for FeatureSet {
+    // Files without a syntax or edition declaration are proto2.
+    fn default() -> Self {
+        Self::proto2()
+    }
+}
+
+impl TryFrom<&Proto> for FeatureSet {
+    type Error = ParserError;
+
+    // File defaults according to the syntax or edition declaration
+    fn try_from(proto: &Proto) -> Result<Self, Self::Error> {
+        if let Some(edition) = proto.edition() {
+            return match edition.value.as_str() {
+                "2023" => Ok(Self::edition2023()),
+                invalid => Err(
+                    ParserError::invalid("edition with known defaults(2023)", invalid)
+                        .with_span(edition.span),
+                ),
+            };
+        }
+
+        match proto.syntax().is_some_and(Syntax::is_proto3) {
+            true => Ok(Self::proto3()),
+            false => Ok(Self::proto2()),
+        }
+    }
+}
+
+// ResolvedFeatures
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ResolvedFeatures {
+    file: FeatureSet,
+    elements: HashMap<String, FeatureSet>,
+}
+
+impl ResolvedFeatures {
+    pub fn new(file: FeatureSet) -> Self {
+        Self {
+            file,
+            elements: HashMap::new(),
+        }
+    }
+
+    pub fn file(&self) -> &FeatureSet {
+        &self.file
+    }
+
+    // Looks up the effective features of an element by its fully qualified name, without the
+    // leading dot, e.g. `pkg.Message.field`. Enum values are scoped by their enum and rpcs by their
+    // service.
+    pub fn get(&self, name: &str) -> Option<&FeatureSet> {
+        self.elements.get(name)
+    }
+
+    fn insert(&mut self, name: String, features: FeatureSet) {
+        self.elements.insert(name, features);
+    }
+}
+
+impl TryFrom<&Proto> for ResolvedFeatures {
+    type Error = ParserError;
+
+    fn try_from(proto: &Proto) -> Result<Self, Self::Error> {
+        let mut resolver = Resolver {
+            editions: proto.edition().is_some(),
+            legacy_proto3: proto.edition().is_none()
+                && proto.syntax().is_some_and(Syntax::is_proto3),
+            resolved: ResolvedFeatures::default(),
+        };
+
+        let file = with_statements(
+            &FeatureSet::try_from(proto)?,
+            proto.members().iter().filter_map(|m| match m {
+                ProtoMember::Option(v) => Some(v),
+                _ => None,
+            }),
+        )?;
+        resolver.resolved.file = file;
+
+        let scope = proto.package().map(|p| p.value.clone()).unwrap_or_default();
+
+        for member in proto.members() {
+            match member {
+                ProtoMember::Enum(v) => resolver.enumerate(&scope, &file, v)?,
+                ProtoMember::Extend(v) => resolver.extend(&scope, &file, v)?,
+                ProtoMember::Message(v) => resolver.message(&scope, &file, v)?,
+                ProtoMember::Service(v) => resolver.service(&scope, &file, v)?,
+                _ => (),
+            }
+        }
+
+        Ok(resolver.resolved)
+    }
+}
+
+// Applies the `features.*` option statements of a body, errors point at the statement.
+fn with_statements<'a, I>(parent: &FeatureSet, options: I) -> Result<FeatureSet, ParserError>
+where
+    I: IntoIterator<Item = &'a OptionField>,
+{
+    let mut res = *parent;
+
+    for option in options {
+        res = res
+            .with_options([(&option.name, &option.value)])
+            .map_err(|e| e.with_span(option.span))?;
+    }
+
+    Ok(res)
+}
+
+fn scoped(scope: &str, name: &str) -> String {
+    match scope.is_empty() {
+        true => name.to_string(),
+        false => format!("{scope}.{name}"),
+    }
+}
+
+struct Resolver {
+    // Legacy `packed` options are replaced by features in editions
+    editions: bool,
+    legacy_proto3: bool,
+    resolved: ResolvedFeatures,
+}
+
+impl Resolver {
+    fn message(
+        &mut self,
+        scope: &str,
+        parent: &FeatureSet,
+        message: &Message,
+    ) -> Result<(), ParserError> {
+        let name = scoped(scope, &message.name);
+        let features = with_statements(
+            parent,
+            message.members.iter().filter_map(|m| match m {
+                MessageMember::Option(v) => Some(v),
+                _ => None,
+            }),
+        )?;
+
+        self.resolved.insert(name.clone(), features);
+
+        for member in &message.members {
+            match member {
+                MessageMember::Enum(v) => self.enumerate(&name, &features, v)?,
+                MessageMember::Extend(v) => self.extend(&name, &features, v)?,
+                MessageMember::Field(v) => self.field(&name, &features, v, false)?,
+                MessageMember::Group(v) => {
+                    let mut group = features;
+                    group.message_encoding = MessageEncoding::Delimited;
+                    self.field(&name, &group, &v.field, false)?;
+                    self.message(&name, &features, &v.message)?;
+                }
+                MessageMember::Map(v) => {
+                    self.field(&name, &features, &v.field(), false)?;
+                    self.message(&name, &features, &v.entry())?;
+                }
+                MessageMember::Message(v) => self.message(&name, &features, v)?,
+                MessageMember::Oneof(v) => self.oneof(&name, &features, v)?,
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    // Oneof members and extensions always have explicit presence.
+    fn field(
+        &mut self,
+        scope: &str,
+        parent: &FeatureSet,
+        field: &Field,
+        explicit: bool,
+    ) -> Result<(), ParserError> {
+        let mut features = *parent;
+
+        // Features implied by the legacy syntax
+        match field.label {
+            Label::Required => features.field_presence = FieldPresence::LegacyRequired,
+            Label::Optional if self.legacy_proto3 => {
+                features.field_presence = FieldPresence::Explicit
+            }
+            _ => (),
+        }
+
+        let options = field.options.iter().flat_map(|o| o.iter());
+        for (name, value) in options.clone() {
+            if name.as_simple() == Some("packed") {
+                if self.editions {
+                    return Err(ParserError::expected(
+                        format!(
+                            "features.repeated_field_encoding in editions, check {FEATURES_URL} \
+                             for more info"
+                        ),
+                        "packed",
+                    )
+                    .with_span(field.span));
+                }
+
+                features.repeated_field_encoding = match value.as_constant() {
+                    Some(Constant::Bool(true)) => RepeatedFieldEncoding::Packed,
+                    _ => RepeatedFieldEncoding::Expanded,
+                };
+            }
+        }
+
+        let mut features = features
+            .with_options(options.map(|(k, v)| (k, v)))
+            .map_err(|e| e.with_span(field.span))?;
+        if explicit {
+            features.field_presence = FieldPresence::Explicit;
+        }
+        self.resolved.insert(scoped(scope, &field.name), features);
+
+        Ok(())
+    }
+
+    fn oneof(
+        &mut self,
+        scope: &str,
+        parent: &FeatureSet,
+        oneof: &Oneof,
+    ) -> Result<(), ParserError> {
+        let name = scoped(scope, &oneof.name);
+        let features = with_statements(
+            parent,
+            oneof.members.iter().filter_map(|m| match m {
+                OneofMember::Option(v) => Some(v),
+                _ => None,
+            }),
+        )?;
+
+        self.resolved.insert(name, features);
+
+        // Oneof fields are part of the message scope
+        for member in &oneof.members {
+            match member {
+                OneofMember::Field(v) => self.field(scope, &features, v, true)?,
+                OneofMember::Group(v) => {
+                    let mut group = features;
+                    group.message_encoding = MessageEncoding::Delimited;
+                    self.field(scope, &group, &v.field, true)?;
+                    self.message(scope, &features, &v.message)?;
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn enumerate(
+        &mut self,
+        scope: &str,
+        parent: &FeatureSet,
+        enm: &Enum,
+    ) -> Result<(), ParserError> {
+        let name = scoped(scope, &enm.name);
+        let features = with_statements(
+            parent,
+            enm.members.iter().filter_map(|m| match m {
+                EnumMember::Option(v) => Some(v),
+                _ => None,
+            }),
+        )?;
+
+        self.resolved.insert(name.clone(), features);
+
+        for member in &enm.members {
+            if let EnumMember::Field(v) = member {
+                let options = v.options.iter().flat_map(|o| o.iter());
+                let value = features
+                    .with_options(options.map(|(k, v)| (k, v)))
+                    .map_err(|e| e.with_span(v.span))?;
+                self.resolved.insert(scoped(&name, &v.name), value);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn extend(
+        &mut self,
+        scope: &str,
+        parent: &FeatureSet,
+        extend: &Extend,
+    ) -> Result<(), ParserError> {
+        // Extensions are declared in the scope enclosing the extend block
+        for member in &extend.members {
+            match member {
+                ExtendMember::Field(v) => self.field(scope, parent, v, true)?,
+                ExtendMember::Group(v) => {
+                    let mut group = *parent;
+                    group.message_encoding = MessageEncoding::Delimited;
+                    self.field(scope, &group, &v.field, true)?;
+                    self.message(scope, parent, &v.message)?;
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn service(
+        &mut self,
+        scope: &str,
+        parent: &FeatureSet,
+        service: &Service,
+    ) -> Result<(), ParserError> {
+        let name = scoped(scope, &service.name);
+        let features = with_statements(
+            parent,
+            service.members.iter().filter_map(|m| match m {
+                ServiceMember::Option(v) => Some(v),
+                _ => None,
+            }),
+        )?;
+
+        self.resolved.insert(name.clone(), features);
+
+        for member in &service.members {
+            if let ServiceMember::Rpc(v) = member {
+                let rpc = with_statements(&features, v.options())?;
+                self.resolved.insert(scoped(&name, &v.name), rpc);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::edition::Edition;
+    use crate::types::enum_field::EnumField;
+    use crate::types::field_option::FieldOption;
+    use crate::types::option_value::MessageLiteral;
+    use crate::types::package::Package;
+    use crate::types::syntax::Syntax;
+    use crate::types::type_ref::{Scalar, TypeKind, TypeRef};
+
+    use super::*;
+
+    fn message(editions: bool) -> Message {
+        let mut message = Message::new("Foo".to_string());
+
+        let mut packed = Field::new(
+            "bar".to_string(),
+            TypeRef::from(Scalar::Int32),
+            1,
+            Label::Repeated,
+        );
+        let mut options = FieldOption::new();
+        options.push(match editions {
+            true => (
+                OptionName::try_from("features.repeated_field_encoding").unwrap(),
+                OptionValue::from(Constant::Ident("EXPANDED".to_string())),
+            ),
+            false => (
+                OptionName::try_from("packed").unwrap(),
+                OptionValue::from(Constant::Bool(false)),
+            ),
+        });
+        packed.set_options(Some(options));
+
+        let mut implicit = Field::new(
+            "baz".to_string(),
+            TypeRef::from(Scalar::String),
+            2,
+            Label::None,
+        );
+        let mut options = FieldOption::new();
+        options.push((
+            OptionName::try_from("features.field_presence").unwrap(),
+            OptionValue::from(Constant::Ident("IMPLICIT".to_string())),
+        ));
+        implicit.set_options(Some(options));
+
+        message.push(MessageMember::from(packed));
+        message.push(MessageMember::from(implicit));
+        message.push(MessageMember::from(Field::new(
+            "qux".to_string(),
+            TypeRef::from(Scalar::String),
+            3,
+            Label::Optional,
+        )));
+
+        message
+    }
+
+    #[test]
+    fn edition_ok() {
+        let mut proto = Proto::new();
+        proto.set_edition(Edition::new("2023".to_string()));
+        proto.set_package(Package::new("a.b".to_string()));
+        proto.push(ProtoMember::from(OptionField::new(
+            OptionName::try_from("features.enum_type").unwrap(),
+            OptionValue::from(Constant::Ident("CLOSED".to_string())),
+        )));
+        proto.push(ProtoMember::from(message(true)));
+
+        let mut enm = Enum::new("Status".to_string());
+        enm.push(EnumMember::from(OptionField::new(
+            OptionName::try_from("features.enum_type").unwrap(),
+            OptionValue::from(Constant::Ident("OPEN".to_string())),
+        )));
+        enm.push(EnumMember::from(EnumField::new("OK".to_string(), 0)));
+        proto.push(ProtoMember::from(enm));
+
+        let res = ResolvedFeatures::try_from(&proto);
+        assert!(res.is_ok(), "feature resolution error {:?}", res);
+
+        let features = res.unwrap();
+        assert!(!features.file().is_open_enum());
+        assert_eq!(
+            features.get("a.b.Foo").unwrap().field_presence,
+            FieldPresence::Explicit
+        );
+        assert_eq!(
+            features.get("a.b.Foo.bar").unwrap().repeated_field_encoding,
+            RepeatedFieldEncoding::Expanded
+        );
+        assert_eq!(
+            features.get("a.b.Foo.baz").unwrap().field_presence,
+            FieldPresence::Implicit
+        );
+        assert!(features.get("a.b.Status").unwrap().is_open_enum());
+        assert!(features.get("a.b.Status.OK").unwrap().is_open_enum());
+    }
+
+    #[test]
+    fn proto3_ok() {
+        let mut proto = Proto::new();
+        proto.set_syntax(Syntax::new("proto3".to_string()));
+        proto.push(ProtoMember::from(message(false)));
+
+        let features = ResolvedFeatures::try_from(&proto).unwrap();
+        assert_eq!(
+            features.get("Foo.baz").unwrap().field_presence,
+            FieldPresence::Implicit
+        );
+        assert_eq!(
+            features.get("Foo.qux").unwrap().field_presence,
+            FieldPresence::Explicit
+        );
+        assert!(features.file().validates_utf8());
+    }
+
+    #[test]
+    fn aggregate_ok() {
+        let mut features = MessageLiteral::new();
+        features.push((
+            "field_presence".to_string(),
+            OptionValue::from(Constant::Ident("IMPLICIT".to_string())),
+        ));
+        features.push((
+            "utf8_validation".to_string(),
+            OptionValue::from(Constant::Ident("NONE".to_string())),
+        ));
+
+        let mut proto = Proto::new();
+        proto.set_edition(Edition::new("2023".to_string()));
+        proto.push(ProtoMember::from(OptionField::new(
+            OptionName::try_from("features").unwrap(),
+            OptionValue::from(features),
+        )));
+
+        let features = ResolvedFeatures::try_from(&proto).unwrap();
+        assert_eq!(features.file().field_presence, FieldPresence::Implicit);
+        assert!(!features.file().validates_utf8());
+    }
+
+    #[test]
+    fn field_presence_ok() {
+        let source = r#"syntax = "proto3";
+message Foo {
+  int32 a = 1;
+  optional int32 b = 2;
+  Bar c = 3;
+  E d = 4;
+  repeated int32 e = 5;
+  oneof o { int32 f = 6; }
+  message Bar {}
+  enum E { X = 0; }
+}
+extend Foo { int32 g = 7; }
+"#;
+        let proto = crate::parse_str("foo.proto", source).unwrap();
+        let features = ResolvedFeatures::try_from(&proto).unwrap();
+
+        let presence = |name: &str, kind| {
+            let field = proto_field(&proto, name);
+            field.has_presence(features.get(&format!("Foo.{name}")).unwrap(), kind)
+        };
+        let extension = |name: &str| {
+            let field = proto_field(&proto, name);
+            field.has_presence(features.get(name).unwrap(), TypeKind::Enum)
+        };
+
+        assert!(!presence("a", TypeKind::Enum));
+        assert!(presence("b", TypeKind::Enum));
+        // Implicit presence only applies to enum and scalar fields
+        assert!(presence("c", TypeKind::Message));
+        assert!(!presence("d", TypeKind::Enum));
+        assert!(!presence("e", TypeKind::Enum));
+        assert!(presence("f", TypeKind::Enum));
+        assert!(extension("g"));
+    }
+
+    #[test]
+    fn field_packed_ok() {
+        let source = r#"edition = "2023";
+message Foo {
+  repeated int32 a = 1;
+  repeated bool b = 2;
+  repeated E c = 3;
+  repeated Foo d = 4;
+  repeated string e = 5;
+  repeated bytes f = 6;
+  int32 g = 7;
+  repeated int32 h = 8 [features.repeated_field_encoding = EXPANDED];
+  enum E { X = 0; }
+}
+"#;
+        let proto = crate::parse_str("foo.proto", source).unwrap();
+        let features = ResolvedFeatures::try_from(&proto).unwrap();
+
+        let packed = |name: &str, kind| {
+            let field = proto_field(&proto, name);
+            field.is_packed(features.get(&format!("Foo.{name}")).unwrap(), kind)
+        };
+
+        assert!(packed("a", TypeKind::Message));
+        assert!(packed("b", TypeKind::Message));
+        assert!(packed("c", TypeKind::Enum));
+        assert!(!packed("d", TypeKind::Message));
+        assert!(!packed("e", TypeKind::Message));
+        assert!(!packed("f", TypeKind::Message));
+        assert!(!packed("g", TypeKind::Message));
+        assert!(!packed("h", TypeKind::Message));
+    }
+
+    #[test]
+    fn unsupported_edition_err() {
+        let mut proto = Proto::new();
+        proto.set_edition(Edition::new("2024".to_string()));
+
+        assert!(FeatureSet::try_from(&proto).is_err());
+    }
+
+    // Field of the first message or extend block named `name`, oneof members included
+    fn proto_field<'a>(proto: &'a Proto, name: &str) -> &'a Field {
+        let mut fields = vec![];
+        for member in proto.members() {
+            match member {
+                ProtoMember::Message(m) => {
+                    for member in &m.members {
+                        match member {
+                            MessageMember::Field(v) => fields.push(v),
+                            MessageMember::Oneof(o) => {
+                                o.members.iter().for_each(|m| {
+                                    if let OneofMember::Field(v) = m {
+                                        fields.push(v)
+                                    }
+                                });
+                            }
+                            _ => (),
+                        }
+                    }
+                }
+                ProtoMember::Extend(e) => e.members.iter().for_each(|m| {
+                    if let ExtendMember::Field(v) = m {
+                        fields.push(v)
+                    }
+                }),
+                _ => (),
+            }
+        }
+
+        fields.into_iter().find(|f| f.name == name).unwrap()
+    }
+
+    #[test]
+    fn invalid_feature_err() {
+        let mut proto = Proto::new();
+        proto.set_edition(Edition::new("2023".to_string()));
+        proto.push(ProtoMember::from(OptionField::new(
+            OptionName::try_from("features.field_presence").unwrap(),
+            OptionValue::from(Constant::Ident("SOMETIMES".to_string())),
+        )));
+
+        assert!(ResolvedFeatures::try_from(&proto).is_err());
+
+        // Errors point at the statement setting the feature
+        let source =
+            "edition = \"2023\";\nmessage A {\n  option features.enum_type = SOMETIMES;\n}";
+        let proto = crate::parse_str("a.proto", source).unwrap();
+        let span = proto.features().unwrap_err().span().unwrap();
+        assert_eq!((span.start().line(), span.start().column()), (3, 3));
+    }
+
+    #[test]
+    fn packed_editions_err() {
+        let source = "edition = \"2023\";\nmessage A {\n  repeated int32 a = 1 [packed = true];\n}";
+        let proto = crate::parse_str("a.proto", source).unwrap();
+
+        let error = proto.features().unwrap_err();
+        assert_eq!(error.code(), "E0005");
+        assert_eq!(error.span().map(|s| s.start().line()), Some(3));
+
+        let proto = crate::parse_str("a.proto", &source.replace("edition = \"2023\"", "")).unwrap();
+        assert!(proto.features().is_ok());
+    }
+}
@@ -1,11 +1,7 @@
 use std::fmt::{Formatter, Result};
 
 pub fn level<'a>(f: &Formatter<'a>) -> usize {
-    if let Some(width) = f.width() {
-        width
-    } else {
-        0
-    }
+    f.width().unwrap_or_default()
 }
 
 pub fn indent<'a>(f: &mut Formatter<'a>) -> Result {
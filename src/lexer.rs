@@ -1,266 +1,436 @@
-use log::{debug, warn};
-use regex::Regex;
-use std::io::Read;
+use log::debug;
 
-use crate::buffer::Buffer;
 use crate::error::LexerError;
-use crate::position::{Point, Position};
-use crate::token::{Token, Type};
-use crate::token_stream::TokenStream;
-
-// Boolean
-const BOOL_LIT: &str = r"(true | false)";
-
-// Identifiers
-const IDENT: &str = r"[a-zA-Z0-9_]+";
-const FULL_IDENT: &str = r"([a-zA-Z0-9_]+[.]?)+";
-const OPT_LIT: &str = r"([a-zA-Z0-9\(\)\.]+)";
-
-// Integer literals
-const DEC_LIT: &str = r"([1-9]?[0-9]+)";
-const OCT_LIT: &str = r"(0[0-7]+)";
-const HEX_LIT: &str = r"(0[xX]?[a-fA-F0-9]+)";
-
-// Floating-point literals
-const FLOAT_LIT1: &str = r"([0-9]+\.[0-9]*([eE]?[+-]?[0-9]+)?)";
-const FLOAT_LIT2: &str = r"([0-9]+([eE]?[+-]?[0-9]+)?)";
-const FLOAT_LIT3: &str = r"(\.[0-9]+([eE]?[+-]?[0-9]+)?)";
-const FLOAT_LIT4: &str = r"(inf | nan)";
-
-// String literals
-//  Hex escape values
-const HEX_ESCAPE: &str = "(\\[xX]{1}[a-fA-F0-9]{2})";
-//  Oct escape values
-const OCT_ESCAPE: &str = "(\\[0-7]{3})";
-// Char escape values
-const CHAR_ESCAPE: &str = "\\[abfnrtv]{1}";
-
-pub struct Lexer {
-    pos: Point,
-
-    ptn_intlit: Regex,
-    ptn_ident: Regex,
-    ptn_full_ident: Regex,
-    ptn_const: Regex,
-    ptn_optlit: Regex,
+use crate::position::{Location, Span};
+use crate::token::{Constant, StringLit, Token, Type};
+
+// Single pass lexer over the raw bytes of a file. Every token is the longest match at the current
+// offset e.g. `foo.bar` is a single full identifier and `1.5e3` a single float literal.
+pub struct Lexer<'a> {
+    input: &'a [u8],
+    loc: Location,
+    // Start of the token being read
+    begin: Location,
 }
 
-impl Lexer {
-    pub fn new() -> Result<Self, LexerError> {
-        let int_lit = format!("({}) | ({}) | ({})", DEC_LIT, OCT_LIT, HEX_LIT);
-        let str_lit_base = format!("({} | {} | {} )", HEX_ESCAPE, OCT_ESCAPE, CHAR_ESCAPE);
-        let str_lit = format!("( \"{}\" | \'{}\' )", str_lit_base, str_lit_base);
-        let float_lit = format!(
-            "( {} | {} | {} | {} )",
-            FLOAT_LIT1, FLOAT_LIT2, FLOAT_LIT3, FLOAT_LIT4
-        );
-        let constant = format!(
-            "{} | [+-]?{} | [+-]?{} | {} | {}",
-            FULL_IDENT, int_lit, float_lit, str_lit, BOOL_LIT
-        );
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            loc: Location::default(),
+            begin: Location::default(),
+        }
+    }
 
-        // Patterns
-        let ptn_const = Regex::new(&constant)?;
-        let ptn_full_ident = Regex::new(FULL_IDENT)?;
-        let ptn_ident = Regex::new(IDENT)?;
-        let ptn_intlit = Regex::new(&int_lit)?;
-        let ptn_optlit = Regex::new(OPT_LIT)?;
-
-        Ok(Lexer {
-            pos: Point::new(0, 0),
-
-            // Patterns
-            ptn_intlit,
-            ptn_ident,
-            ptn_full_ident,
-            ptn_const,
-            ptn_optlit,
-        })
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.loc.offset()).copied()
     }
 
-    fn range(&self, stash: &str) -> Position {
-        Position::range(self.pos) + stash.len()
+    fn peek_at(&self, n: usize) -> Option<u8> {
+        self.input.get(self.loc.offset() + n).copied()
     }
 
-    fn is_discardable(&self, ch: &char) -> bool {
-        matches!(ch, '\n' | '\r' | '\t' | ' ')
+    fn bump(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.loc.advance(byte);
+
+        Some(byte)
     }
 
-    fn is_match(ptn: &Regex, stash: &str) -> bool {
-        if let Some(matched) = ptn.find(stash) {
-            if stash.eq(matched.as_str()) {
-                return true;
-            }
+    fn bump_while(&mut self, f: impl Fn(u8) -> bool) {
+        while self.peek().is_some_and(&f) {
+            self.bump();
         }
-        return false;
     }
 
-    fn match_literals(&self, stash: &str) -> Type {
-        // NOTE: The order in this list will dictate the token priority. Changing the order can
-        // cause some tokens to become unreachable.
+    fn is_ident_start(byte: u8) -> bool {
+        byte.is_ascii_alphabetic() || byte == b'_'
+    }
 
-        match stash {
-            "true" => return Type::BoolLit(true),
-            "false" => return Type::BoolLit(false),
-            _ => (),
-        }
+    fn is_ident_char(byte: u8) -> bool {
+        byte.is_ascii_alphanumeric() || byte == b'_'
+    }
 
-        if Self::is_match(&self.ptn_intlit, stash) {
-            match stash.parse::<i32>() {
-                Ok(v) => {
-                    return Type::IntLit(v);
-                }
-                Err(e) => {
-                    warn!("failed to convert string to int literal {e}");
-                    return Type::Illegal;
-                }
-            }
-        }
+    fn lexeme(&self, start: usize) -> String {
+        String::from_utf8_lossy(&self.input[start..self.loc.offset()]).to_string()
+    }
 
-        if Self::is_match(&self.ptn_ident, stash) {
-            return Type::Ident(stash.to_string());
+    fn invalid(&self, start: usize, reason: &str) -> LexerError {
+        LexerError::InvalidToken {
+            lexeme: self.lexeme(start),
+            reason: reason.to_string(),
+            span: Span::new(self.begin, self.loc),
         }
+    }
 
-        if Self::is_match(&self.ptn_full_ident, stash) {
-            return Type::FullIdent(stash.to_string());
+    fn invalid_literal(&self, start: usize, reason: &str) -> LexerError {
+        LexerError::InvalidLiteral {
+            literal: self.lexeme(start),
+            reason: reason.to_string(),
+            span: Some(Span::new(self.begin, self.loc)),
         }
+    }
 
-        if Self::is_match(&self.ptn_const, stash) {
-            return Type::Constant(stash.to_string());
+    fn skip_whitespace(&mut self) {
+        self.bump_while(|b| matches!(b, b' ' | b'\t' | b'\r' | b'\n' | 0x0b | 0x0c));
+    }
+
+    // Identifiers, keywords and dot separated full identifiers which may start with a dot when
+    // fully-qualified e.g. `.foo.Bar`.
+    fn ident(&mut self, start: usize) -> Type {
+        if self.peek() == Some(b'.') {
+            self.bump();
         }
+        self.bump_while(Self::is_ident_char);
 
-        if Self::is_match(&self.ptn_optlit, stash) {
-            return Type::OptionName(stash.to_string());
+        while self.peek() == Some(b'.') && self.peek_at(1).is_some_and(Self::is_ident_start) {
+            self.bump();
+            self.bump_while(Self::is_ident_char);
         }
 
-        return Type::Illegal;
+        Self::word(self.lexeme(start))
     }
 
-    pub fn tokenize(&self, ch: char, stash: &mut String) -> Option<Token> {
-        // Ignored characters
-        if self.is_discardable(&ch) {
-            return None;
+    fn word(value: String) -> Type {
+        if value.contains('.') {
+            return Type::FullIdent(value);
         }
 
-        // Match single character tokens
-        if stash.is_empty() {
-            match Type::from(&ch) {
-                Type::Illegal => (),
-                typ => return Some(Token::from(typ)),
-            }
+        match value.as_str() {
+            "true" => Type::BoolLit(true),
+            "false" => Type::BoolLit(false),
+            v => match Type::from(v) {
+                Type::Illegal => Type::Ident(value),
+                keyword => keyword,
+            },
         }
+    }
+
+    // Decimal, octal, hexadecimal and floating-point literals, anything glued to a number e.g.
+    // `1foo` is rejected rather than split into two tokens.
+    fn number(&mut self, start: usize) -> Result<Type, LexerError> {
+        if self.peek() == Some(b'0') && matches!(self.peek_at(1), Some(b'x' | b'X')) {
+            self.bump();
+            self.bump();
+        } else {
+            self.bump_while(|b| b.is_ascii_digit());
+
+            if self.peek() == Some(b'.') {
+                self.bump();
+                self.bump_while(|b| b.is_ascii_digit());
+            }
 
-        // Stash character to build multi-character tokens
-        stash.push(ch);
+            if matches!(self.peek(), Some(b'e' | b'E')) {
+                self.bump();
+                if matches!(self.peek(), Some(b'+' | b'-')) {
+                    self.bump();
+                }
+            }
+        }
+        self.bump_while(|b| Self::is_ident_char(b) || b == b'.');
 
-        // Match keywords against stash
-        match Type::from(stash.as_str()) {
-            Type::Illegal => (),
-            typ => return Some(Token::from(typ)),
+        let value = self.lexeme(start);
+        if let Some(v) = Constant::parse_int(&value) {
+            return Ok(Type::IntLit(v));
         }
 
-        match self.match_literals(stash) {
-            Type::Illegal => (),
-            typ => return Some(Token::from(typ)),
+        if let Some(v) = Constant::parse_float(&value) {
+            return Ok(Type::FloatLit(v));
         }
 
-        return None;
+        match value.bytes().all(|b| b.is_ascii_digit()) {
+            true => Err(self.invalid_literal(start, "integer literal out of range")),
+            false => Err(self.invalid_literal(start, "invalid numeric literal")),
+        }
     }
 
-    pub fn next_token<T>(&mut self, buf: &mut Buffer<T>) -> Result<Token, LexerError>
-    where
-        T: Read,
-    {
-        let mut stash = String::new();
-        while let Some(chars) = buf.next() {
-            for ch in chars {
-                if ch.eq(&'\n') {
-                    self.pos *= 0;
-                }
+    fn string(&mut self, start: usize) -> Result<Type, LexerError> {
+        let quote = self.bump();
 
-                let mut token = match self.tokenize(ch, &mut stash) {
-                    Some(v) => v,
-                    None => {
-                        debug!("stashing: {ch}, stash: {stash}");
-                        continue;
+        loop {
+            match self.peek() {
+                None | Some(b'\n') => {
+                    return Err(self.invalid_literal(start, "unterminated string literal"))
+                }
+                Some(b'\\') => {
+                    self.bump();
+                    if self.peek().is_some_and(|b| b != b'\n') {
+                        self.bump();
+                    }
+                }
+                byte => {
+                    self.bump();
+                    if byte == quote {
+                        break;
                     }
-                };
-
-                if token.typ().eq(&Type::Illegal) {
-                    debug!("invalid token: '{stash}' {}", self.range(&stash));
-                    return Err(LexerError::Invalid(stash.clone()));
                 }
-
-                // Complete the token by assigning it the correct position
-                let pos = self.range(&stash);
-                debug!("identified token '{token}' and updating its position to {pos}");
-                token.set_position(pos);
-
-                return Ok(token);
             }
         }
 
-        return Err(LexerError::Invalid(format!("stash: {stash}")));
+        let raw = std::str::from_utf8(&self.input[start..self.loc.offset()])
+            .map_err(|_| self.invalid_literal(start, "string literal is not valid UTF-8"))?;
+
+        let span = Span::new(self.begin, self.loc);
+        let lit = StringLit::try_from(raw).map_err(|e| e.with_span(span))?;
+
+        Ok(Type::StringLit(lit))
     }
 
-    pub fn token_stream<T>(&mut self, mut buf: Buffer<T>) -> Result<TokenStream, LexerError>
-    where
-        T: Read,
-    {
-        let mut tokens = TokenStream::new();
-        let eof = Token::from(Type::EOF);
+    fn comment(&mut self, start: usize) -> Result<Type, LexerError> {
+        self.bump();
 
-        loop {
-            match self.next_token(&mut buf) {
-                Ok(token) => {
-                    if token == eof {
-                        break;
+        match self.bump() {
+            Some(b'/') => {
+                self.bump_while(|b| b != b'\n');
+                let text = &self.input[start + 2..self.loc.offset()];
+                Ok(Type::LineComment(String::from_utf8_lossy(text).to_string()))
+            }
+            _ => loop {
+                match self.peek() {
+                    None => return Err(self.invalid(start, "unterminated block comment")),
+                    Some(b'*') if self.peek_at(1) == Some(b'/') => {
+                        self.bump();
+                        self.bump();
+
+                        let text = &self.input[start + 2..self.loc.offset() - 2];
+                        return Ok(Type::BlockComment(
+                            String::from_utf8_lossy(text).to_string(),
+                        ));
                     }
+                    _ => {
+                        self.bump();
+                    }
+                }
+            },
+        }
+    }
 
-                    tokens.push(token);
+    pub fn next_token(&mut self) -> Result<Token, LexerError> {
+        self.skip_whitespace();
+
+        let begin = self.loc;
+        let start = begin.offset();
+        self.begin = begin;
+
+        let byte = match self.peek() {
+            Some(v) => v,
+            None => return Ok(Token::new(Type::Eof, Span::new(begin, begin))),
+        };
+
+        let next = self.peek_at(1);
+
+        let typ = match byte {
+            b if Self::is_ident_start(b) => self.ident(start),
+            b'.' if next.is_some_and(Self::is_ident_start) => self.ident(start),
+            b'.' if next.is_some_and(|b| b.is_ascii_digit()) => self.number(start)?,
+            b if b.is_ascii_digit() => self.number(start)?,
+            b'"' | b'\'' => self.string(start)?,
+            b'/' if matches!(next, Some(b'/' | b'*')) => self.comment(start)?,
+            // Slashes outside of comments only appear in Any type URLs of message literals e.g.
+            // `[type.googleapis.com/foo.Bar]`
+            b'/' => {
+                self.bump();
+                Type::Slash
+            }
+            b => {
+                self.bump();
+                match Type::from(&(b as char)) {
+                    Type::Illegal | Type::Asterisk => {
+                        return Err(self.invalid(start, "unexpected character"))
+                    }
+                    typ => typ,
                 }
-                Err(e) => return Err(e),
             }
+        };
+
+        let token = Token::new(typ, Span::new(begin, self.loc));
+        debug!("identified token '{token}'");
+
+        Ok(token)
+    }
+
+    // Every token up to the end of the input, adjacent string literals are left apart.
+    pub fn tokens(mut self) -> Result<Vec<Token>, LexerError> {
+        let mut tokens = vec![];
+
+        loop {
+            let token = self.next_token()?;
+            if token.typ() == &Type::Eof {
+                break;
+            }
+
+            tokens.push(token);
         }
 
-        return Ok(tokens);
+        Ok(tokens)
     }
 }
 
-//#[cfg(test)]
-//mod tests {
-//    use super::*;
-//    use std::io::BufReader;
-//    use std::io::Cursor;
-//
-//    #[test]
-//    fn new() {
-//        let file = Cursor::new(String::from("foobar"));
-//        let inner = BufReader::new(file);
-//        let buf = Buffer::new(inner);
-//
-//        Lexer::new(buf);
-//    }
-//
-//    #[test]
-//    fn syntax() {
-//        let pb = "syntax = \"proto3\'";
-//        let cursor = Cursor::new(pb);
-//        let bf = BufReader::new(cursor);
-//        let inner = Buffer::new(bf);
-//
-//        let mut lexer = Lexer::new(inner);
-//
-//        let mut expected = TokenStream::new();
-//        expected.push(Token::from(Type::Syntax));
-//        expected.push(Token::from(Type::Assign));
-//        expected.push(Token::constant("proto3".to_string()));
-//
-//        let actual = match lexer.token_stream() {
-//            Ok(v) => v,
-//            Err(e) => panic!("got error '{e }'"),
-//        };
-//
-//        assert_eq!(expected, actual);
-//    }
-//}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types(input: &str) -> Vec<Type> {
+        let tokens = Lexer::new(input.as_bytes()).tokens().unwrap();
+        tokens.iter().map(|t| t.typ().clone()).collect()
+    }
+
+    fn ident(v: &str) -> Type {
+        Type::Ident(v.to_string())
+    }
+
+    #[test]
+    fn syntax_ok() {
+        assert_eq!(
+            types("syntax = \"proto3\";"),
+            vec![
+                Type::Syntax,
+                Type::Assign,
+                Type::StringLit(StringLit::try_from("\"proto3\"").unwrap()),
+                Type::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn number_ok() {
+        assert_eq!(
+            types("0x1F 017 0 42 1.5 .5e3 1e10 2. inf -3"),
+            vec![
+                Type::IntLit(31),
+                Type::IntLit(15),
+                Type::IntLit(0),
+                Type::IntLit(42),
+                Type::FloatLit(1.5),
+                Type::FloatLit(500.0),
+                Type::FloatLit(1e10),
+                Type::FloatLit(2.0),
+                ident("inf"),
+                Type::Minus,
+                Type::IntLit(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn ident_ok() {
+        assert_eq!(
+            types("message foo.Bar .baz.Qux true max_value"),
+            vec![
+                Type::Message,
+                Type::FullIdent("foo.Bar".to_string()),
+                Type::FullIdent(".baz.Qux".to_string()),
+                Type::BoolLit(true),
+                ident("max_value"),
+            ]
+        );
+    }
+
+    #[test]
+    fn option_name_ok() {
+        assert_eq!(
+            types("option (foo.bar).baz = 1; int32 a = 1 [(b) = 2, c = 3]; rpc A(B)"),
+            vec![
+                Type::Option,
+                Type::LParen,
+                Type::FullIdent("foo.bar".to_string()),
+                Type::RParen,
+                Type::FullIdent(".baz".to_string()),
+                Type::Assign,
+                Type::IntLit(1),
+                Type::Semicolon,
+                ident("int32"),
+                ident("a"),
+                Type::Assign,
+                Type::IntLit(1),
+                Type::LBrack,
+                Type::LParen,
+                ident("b"),
+                Type::RParen,
+                Type::Assign,
+                Type::IntLit(2),
+                Type::Comma,
+                ident("c"),
+                Type::Assign,
+                Type::IntLit(3),
+                Type::RBrack,
+                Type::Semicolon,
+                Type::Rpc,
+                ident("A"),
+                Type::LParen,
+                ident("B"),
+                Type::RParen,
+            ]
+        );
+
+        assert_eq!(
+            types("(foo) . bar"),
+            vec![
+                Type::LParen,
+                ident("foo"),
+                Type::RParen,
+                Type::Dot,
+                ident("bar")
+            ]
+        );
+    }
+
+    #[test]
+    fn string_and_comment_ok() {
+        assert_eq!(
+            types("\"a // b;\" 'c' // don't\n/* x\n * y */ foo"),
+            vec![
+                Type::StringLit(StringLit::try_from("\"a // b;\"").unwrap()),
+                Type::StringLit(StringLit::try_from("'c'").unwrap()),
+                Type::LineComment(" don't".to_string()),
+                Type::BlockComment(" x\n * y ".to_string()),
+                ident("foo"),
+            ]
+        );
+    }
+
+    #[test]
+    fn span_ok() {
+        let tokens = Lexer::new("foo\n  'é' \"x\"\n/* a\n */ bar".as_bytes())
+            .tokens()
+            .unwrap();
+
+        let spans: Vec<String> = tokens.iter().map(|t| format!("{}", t.span())).collect();
+        assert_eq!(
+            spans,
+            [
+                "1:1 -> 1:4",
+                "2:3 -> 2:6",
+                "2:7 -> 2:10",
+                "3:1 -> 4:4",
+                "4:5 -> 4:8"
+            ]
+        );
+        assert_eq!(tokens[1].span().start().offset(), 6);
+        assert_eq!(tokens[1].span().len(), 4);
+    }
+
+    #[test]
+    fn invalid_err() {
+        for input in [
+            "1foo",
+            "09",
+            "0x",
+            "\"abc",
+            "'a\nb'",
+            "/* x",
+            "@",
+        ] {
+            let res = Lexer::new(input.as_bytes()).tokens();
+            assert!(res.is_err(), "accepted '{input}'");
+        }
+
+        let err = Lexer::new("foo \"a\\qb\";".as_bytes()).tokens().unwrap_err();
+        assert_eq!(err.code(), "E0004");
+        assert_eq!(format!("{}", err.span().unwrap()), "1:5 -> 1:11");
+
+        let err = Lexer::new("foo\n  @".as_bytes()).tokens().unwrap_err();
+        assert_eq!(err.code(), "E0003");
+        assert_eq!(format!("{}", err.span().unwrap()), "2:3 -> 2:4");
+    }
+}
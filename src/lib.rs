@@ -1,44 +1,267 @@
-use std::{fs::File, io::BufReader};
-use error::ProtobufError;
-use parser::Parser;
+use std::io::Read;
 
-use crate::buffer::Buffer;
-use crate::types::proto::Proto;
+use tokio::io::{AsyncRead, AsyncReadExt};
 
-mod buffer;
+use crate::parser::Parser;
+
+pub use crate::cst::{Cst, SyntaxToken, Trivia};
+pub use crate::diagnostic::Diagnostic;
+pub use crate::error::{BufferError, LexerError, ParserError, ProtobufError};
+pub use crate::features::{
+    EnumType, FeatureSet, FieldPresence, JsonFormat, MessageEncoding, RepeatedFieldEncoding,
+    ResolvedFeatures, Utf8Validation,
+};
+pub use crate::position::{Location, Span};
+pub use crate::render::Renderer;
+#[cfg(feature = "tar")]
+pub use crate::resolver::TarResolver;
+#[cfg(feature = "zip")]
+pub use crate::resolver::ZipResolver;
+pub use crate::resolver::{ChainedResolver, DirectoryResolver, SourceResolver};
+pub use crate::token::{Constant, StringLit};
+pub use crate::workspace::{Dependency, ImportGraph, Workspace};
+
+// AST
+pub use crate::types::comment::{BlockComment, LineComment};
+pub use crate::types::edition::Edition;
+pub use crate::types::empty::Empty;
+pub use crate::types::enum_field::EnumField;
+pub use crate::types::enumerate::{Enum, EnumMember};
+pub use crate::types::extend::{Extend, ExtendMember};
+pub use crate::types::extension_range::ExtensionRange;
+pub use crate::types::field::{Field, Label};
+pub use crate::types::field_option::FieldOption;
+pub use crate::types::group::Group;
+pub use crate::types::import::{Import, ImportScope};
+pub use crate::types::map::Map;
+pub use crate::types::message::{Message, MessageMember};
+pub use crate::types::oneof::{Oneof, OneofMember};
+pub use crate::types::option_field::OptionField;
+pub use crate::types::option_name::{OptionName, OptionNamePart};
+pub use crate::types::option_value::{MessageLiteral, OptionValue, OptionValueKind};
+pub use crate::types::package::Package;
+pub use crate::types::proto::{Proto, ProtoMember};
+pub use crate::types::reserved::{NumberRange, RangeEnd, Reserved, ReservedKind};
+pub use crate::types::rpc::{Rpc, RpcMember};
+pub use crate::types::service::{Service, ServiceMember};
+pub use crate::types::syntax::Syntax;
+pub use crate::types::type_ref::{Scalar, TypeRef, TypeRefKind};
+
+mod cst;
+mod diagnostic;
 mod error;
+mod features;
 mod indent;
 mod lexer;
 mod parser;
 mod position;
+mod render;
+mod resolver;
+mod scope;
 mod token;
 mod token_stream;
 mod types;
+mod workspace;
+
+// Parses the source of a single file, `name` is kept as the file name of the result.
+pub fn parse_str(name: &str, source: &str) -> Result<Proto, ProtobufError> {
+    let mut proto = parse_bytes(source.as_bytes())?;
+    proto.set_name(name);
+
+    Ok(proto)
+}
+
+// Reads the whole input before parsing it, read failures are returned as IO errors.
+pub fn parse_reader(mut reader: impl Read) -> Result<Proto, ProtobufError> {
+    let mut source = vec![];
+    reader.read_to_end(&mut source)?;
 
+    parse_bytes(&source)
+}
+
+pub fn parse_bytes(bytes: &[u8]) -> Result<Proto, ProtobufError> {
+    let (proto, diagnostics) = parse_source(bytes)?;
+
+    match diagnostics.into_iter().next() {
+        Some(diagnostic) => Err(diagnostic.into_error().into()),
+        None => Ok(proto),
+    }
+}
+
+// Parse failures are returned as `ProtobufError::File` holding the source to render them with.
 pub fn load_file(filename: &str) -> Result<Proto, ProtobufError> {
-    let file = File::open(filename).expect("open file");
-    let inner = BufReader::new(file);
-    let buf = Buffer::new(inner);
-
-    let parser = Parser::new();
-
-    Ok(parser.load(buf)?)
-}
-
-// TODO fix me
-// #[cfg(test)]
-// mod tests {
-//     use crate::load_file;
-// 
-//     #[test]
-//     fn example_file() {
-//         let p = load_file("example.proto");
-//         assert!(p.is_ok(), "failed to load file");
-//     }
-// 
-//     #[test]
-//     fn example_to_string() {
-//         let p = load_file("example.proto");
-//         assert!(p.is_ok(), "failed to load string");
-//     }
-// }
+    let source = std::fs::read(filename)?;
+
+    parse_file(filename, &source)
+}
+
+// Reads the file without blocking the runtime, parsing runs on the blocking thread pool. Input is
+// read into a buffer owned by the future so dropping it at any point leaves nothing behind.
+pub async fn load_file_async(filename: &str) -> Result<Proto, ProtobufError> {
+    let source = tokio::fs::read(filename).await?;
+    let name = filename.to_string();
+
+    parse_blocking(move || parse_file(&name, &source)).await
+}
+
+pub async fn parse_async_reader(
+    mut reader: impl AsyncRead + Unpin,
+) -> Result<Proto, ProtobufError> {
+    let mut source = vec![];
+    reader.read_to_end(&mut source).await?;
+
+    parse_blocking(move || parse_bytes(&source)).await
+}
+
+async fn parse_blocking(
+    parse: impl FnOnce() -> Result<Proto, ProtobufError> + Send + 'static,
+) -> Result<Proto, ProtobufError> {
+    tokio::task::spawn_blocking(parse)
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+fn parse_file(name: &str, source: &[u8]) -> Result<Proto, ProtobufError> {
+    let mut proto = parse_bytes(source).map_err(|e| file_error(name, source, e))?;
+    proto.set_name(name);
+
+    Ok(proto)
+}
+
+// Loads as much of the file as possible together with every problem found in it.
+pub fn load_file_partial(filename: &str) -> Result<(Proto, Vec<Diagnostic>), ProtobufError> {
+    let source = std::fs::read(filename)?;
+
+    let (mut proto, diagnostics) =
+        parse_source(&source).map_err(|e| file_error(filename, &source, e))?;
+    proto.set_name(filename);
+
+    Ok((proto, diagnostics))
+}
+
+// Every parse goes through here, only problems which stop tokenizing the source fail it.
+fn parse_source(source: &[u8]) -> Result<(Proto, Vec<Diagnostic>), ProtobufError> {
+    let cst = Cst::try_from(source)?;
+
+    Ok(Parser::new().parse_partial(cst))
+}
+
+fn file_error(name: &str, source: &[u8], error: ProtobufError) -> ProtobufError {
+    ProtobufError::File {
+        name: name.to_string(),
+        text: String::from_utf8_lossy(source).into_owned(),
+        error: Box::new(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        load_file, load_file_async, load_file_partial, parse_async_reader, parse_bytes,
+        parse_reader, parse_str, ProtobufError, ProtoMember,
+    };
+
+    #[test]
+    fn example_file() {
+        let p = load_file("example.proto");
+        assert!(p.is_ok(), "failed to load file {:?}", p);
+        assert_eq!(p.unwrap().name(), "example.proto");
+    }
+
+    #[test]
+    fn missing_file_err() {
+        let res = load_file("missing.proto");
+        assert!(matches!(res, Err(ProtobufError::IO(_))), "{:?}", res);
+    }
+
+    #[test]
+    fn parse_ok() {
+        let source = "syntax = \"proto3\";\nmessage Foo { string bar = 1; }\n";
+
+        let proto = parse_str("foo.proto", source).unwrap();
+        assert_eq!(proto.name(), "foo.proto");
+        match proto.members() {
+            [ProtoMember::Message(m)] => assert_eq!(m.name, "Foo"),
+            invalid => panic!("unexpected members {:?}", invalid),
+        }
+
+        assert_eq!(parse_bytes(source.as_bytes()).unwrap().members(), proto.members());
+        assert_eq!(parse_reader(source.as_bytes()).unwrap().members(), proto.members());
+    }
+
+    #[tokio::test]
+    async fn async_ok() {
+        let proto = load_file_async("example.proto").await.unwrap();
+        assert_eq!(proto.name(), "example.proto");
+        assert_eq!(proto.members(), load_file("example.proto").unwrap().members());
+
+        let res = load_file_async("missing.proto").await;
+        assert!(matches!(res, Err(ProtobufError::IO(_))), "{:?}", res);
+
+        let source = "message Foo { string bar 1; }";
+        let res = parse_async_reader(source.as_bytes()).await;
+        assert_eq!(res.unwrap_err().code(), "E0005");
+    }
+
+    #[tokio::test]
+    async fn async_cancel_ok() {
+        use tokio::io::AsyncWriteExt;
+
+        // The writer is never closed so reading only stops when the future is dropped
+        let (mut writer, reader) = tokio::io::duplex(64);
+        writer.write_all(b"syntax = \"proto3\";").await.unwrap();
+
+        let timeout = std::time::Duration::from_millis(10);
+        let res = tokio::time::timeout(timeout, parse_async_reader(reader)).await;
+        assert!(res.is_err());
+
+        // Dropping the future released the reader
+        assert!(writer.write_all(b"message Foo {}").await.is_err());
+    }
+
+    #[test]
+    fn parse_err() {
+        let res = parse_str("foo.proto", "message Foo { string bar 1; }");
+        assert_eq!(res.unwrap_err().code(), "E0005");
+
+        let res = parse_bytes(b"syntax = \"proto3\";\n\xff");
+        assert_eq!(res.unwrap_err().code(), "E0002");
+    }
+
+    #[test]
+    fn partial_ok() {
+        let path = std::env::temp_dir().join(format!("protobuf-rs-partial-{}", std::process::id()));
+        let name = path.to_str().unwrap();
+
+        std::fs::write(&path, "message A { int32 a 1; }\nmessage B {}\n").unwrap();
+        let (proto, diagnostics) = load_file_partial(name).unwrap();
+        assert_eq!(proto.name(), name);
+        assert_eq!(proto.members().len(), 2);
+        assert_eq!(diagnostics.len(), 1);
+
+        // Only the first problem is returned when parsing all at once
+        let error = load_file(name).unwrap_err();
+        assert_eq!(error.span(), Some(diagnostics[0].span()));
+
+        std::fs::write(&path, b"message A {}\n\xff").unwrap();
+        let res = load_file_partial(name);
+        std::fs::remove_file(&path).unwrap();
+        match res {
+            Err(ProtobufError::File { text, error, .. }) => {
+                assert_eq!(error.code(), "E0002");
+                assert!(text.starts_with("message A {}"), "{text}");
+            }
+            invalid => panic!("unexpected result {:?}", invalid),
+        }
+    }
+
+    #[test]
+    fn example_to_string() {
+        let p = load_file("example.proto").unwrap();
+        let s: String = p.members().iter().map(|m| m.to_string()).collect();
+
+        assert_eq!(p.package().unwrap().value, "a.b.c");
+        assert!(s.contains("option rcpOpt = \"foo\";"), "{s}");
+        assert!(s.contains("map<int32, string> my_map = 4;"), "{s}");
+    }
+}
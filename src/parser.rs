@@ -1,8 +1,5 @@
-use std::io::Read;
-
-use crate::buffer::Buffer;
-use crate::error::ParserError;
-use crate::lexer::Lexer;
+use crate::cst::Cst;
+use crate::diagnostic::Diagnostic;
 use crate::types::proto::Proto;
 
 pub struct Parser {
@@ -13,26 +10,32 @@ impl Parser {
         Self {}
     }
 
-    pub fn load<T>(&self, buf: Buffer<T>) -> Result<Proto, ParserError>
-    where
-        T: Read,
-    {
-        let mut lexer = Lexer::new()?;
-        let tokens = lexer.token_stream(buf)?;
+    // Partial AST parsed from the tokens of `cst`, which is kept in it to look up node spans.
+    // Statements which failed to parse are left out and reported as diagnostics.
+    pub fn parse_partial(&self, cst: Cst) -> (Proto, Vec<Diagnostic>) {
+        let mut tokens = cst.token_stream();
+        let mut proto = Proto::from(&mut tokens);
+        proto.set_cst(cst);
 
-        Proto::try_from(tokens)
+        (proto, tokens.take_diagnostics())
     }
 }
 
 
-//#[cfg(test)]
-//mod tests {
-//    use std::fs::File;
-//    use std::io::BufReader;
-//
-//    use super::*;
-//
-//    #[test]
-//    fn new_from_file() {
-//    }
-//}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_partial_ok() {
+        let source = "message A { int32 a 1; }\nmessage B {}\nenum E { X = ; }\n";
+        let cst = Cst::try_from(source).unwrap();
+
+        let (proto, diagnostics) = Parser::new().parse_partial(cst);
+        // Bad statements are skipped inside of the bodies, every type is kept
+        assert_eq!(proto.members().len(), 3);
+        assert_eq!(diagnostics.len(), 2, "{diagnostics:?}");
+        assert_eq!(diagnostics[1].span().start().line(), 3);
+        assert_eq!(proto.cst().unwrap().to_string(), source);
+    }
+}
@@ -1,345 +1,140 @@
 use std::fmt::Display;
-use std::ops::{Add, AddAssign, Mul, MulAssign};
 
-// Point
+// Location
+//
+// Byte offset into the source together with its 1-based line and column, the column counts
+// characters rather than bytes.
 
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
-pub struct Point(usize, usize);
-
-impl Point {
-    pub fn new(line: usize, character: usize) -> Self {
-        Self(line, character)
-    }
-
-    pub fn line(&self) -> usize {
-        self.0
-    }
-
-    pub fn char(&self) -> usize {
-        self.1
-    }
-}
-
-impl Add<usize> for Point {
-    type Output = Point;
-
-    fn add(self, rhs: usize) -> Self::Output {
-        Point(self.0, self.1 + rhs)
-    }
-}
-
-impl Mul<usize> for Point {
-    type Output = Point;
-
-    fn mul(self, rhs: usize) -> Self::Output {
-        Point(self.0 + 1, rhs)
-    }
-}
-
-impl AddAssign<usize> for Point {
-    fn add_assign(&mut self, rhs: usize) {
-        self.1 += rhs;
-    }
-}
-
-impl MulAssign<usize> for Point {
-    fn mul_assign(&mut self, rhs: usize) {
-        self.0 += 1;
-        self.1 = rhs;
-    }
-}
-
-impl Display for Point {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "(L{},C{})", self.0, self.1)
-    }
-}
-
-// Range
-
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
-pub struct Range {
-    begin: Point,
-    end: Point,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Location {
+    offset: usize,
+    line: usize,
+    column: usize,
 }
 
-impl Range {
-    pub fn new(begin: Point) -> Self {
-        Range {
-            begin,
-            end: begin.clone(),
+impl Location {
+    pub fn new(offset: usize, line: usize, column: usize) -> Self {
+        Self {
+            offset,
+            line,
+            column,
         }
     }
 
-    pub fn line(&self) -> usize {
-        self.end.line()
-    }
-
-    pub fn char(&self) -> usize {
-        self.end.char()
+    pub fn offset(&self) -> usize {
+        self.offset
     }
-}
 
-impl From<Point> for Range {
-    fn from(begin: Point) -> Self {
-        Self::new(begin)
+    pub fn line(&self) -> usize {
+        self.line
     }
-}
-
-impl Add<usize> for Range {
-    type Output = Self;
 
-    fn add(self, rhs: usize) -> Self::Output {
-        Range {
-            begin: self.begin,
-            end: self.end + rhs,
-        }
+    pub fn column(&self) -> usize {
+        self.column
     }
-}
 
-impl Mul<usize> for Range {
-    type Output = Self;
+    // Moves past `byte`, UTF-8 continuation bytes don't advance the column.
+    pub fn advance(&mut self, byte: u8) {
+        self.offset += 1;
 
-    fn mul(self, rhs: usize) -> Self::Output {
-        Range {
-            begin: self.begin,
-            end: self.end * rhs,
+        match byte {
+            b'\n' => {
+                self.line += 1;
+                self.column = 1;
+            }
+            b if b & 0xc0 == 0x80 => (),
+            _ => self.column += 1,
         }
     }
 }
 
-impl AddAssign<usize> for Range {
-    fn add_assign(&mut self, rhs: usize) {
-        self.end += rhs;
-    }
-}
-
-impl MulAssign<usize> for Range {
-    fn mul_assign(&mut self, rhs: usize) {
-        self.end *= rhs;
+impl Default for Location {
+    fn default() -> Self {
+        Self::new(0, 1, 1)
     }
 }
 
-impl Display for Range {
+impl Display for Location {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} -> {}", self.begin, self.end)
+        write!(f, "{}:{}", self.line, self.column)
     }
 }
 
-// Position
-
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum Position {
-    Point(Point),
-    Range(Range),
-}
-
-impl Position {
-    pub fn range(point: Point) -> Self {
-        Self::Range(Range::from(point))
-    }
-
-    pub fn line(&self) -> usize {
-        match self {
-            Self::Point(v) => v.line(),
-            Self::Range(v) => v.line(),
-        }
-    }
-
-    pub fn char(&self) -> usize {
-        match self {
-            Self::Point(v) => v.char(),
-            Self::Range(v) => v.char(),
-        }
-    }
-}
+// Span
+//
+// Source range of a token or node, `end` points right after the last byte.
 
-impl Default for Position {
-    fn default() -> Self {
-        Self::from(Point::default())
-    }
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    start: Location,
+    end: Location,
 }
 
-impl From<Point> for Position {
-    fn from(value: Point) -> Self {
-        Self::Point(value)
+impl Span {
+    pub fn new(start: Location, end: Location) -> Self {
+        Self { start, end }
     }
-}
 
-impl From<Range> for Position {
-    fn from(value: Range) -> Self {
-        Self::Range(value)
+    pub fn start(&self) -> Location {
+        self.start
     }
-}
 
-impl Add<usize> for Position {
-    type Output = Self;
-
-    fn add(self, rhs: usize) -> Self::Output {
-        match self {
-            Self::Point(v) => Self::Point(v + rhs),
-            Self::Range(v) => Self::Range(v + rhs),
-        }
+    pub fn end(&self) -> Location {
+        self.end
     }
-}
 
-impl Mul<usize> for Position {
-    type Output = Self;
-
-    fn mul(self, rhs: usize) -> Self::Output {
-        match self {
-            Self::Point(v) => Self::Point(v * rhs),
-            Self::Range(v) => Self::Range(v * rhs),
-        }
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
     }
-}
 
-impl AddAssign<usize> for Position {
-    fn add_assign(&mut self, rhs: usize) {
-        match self {
-            Self::Point(v) => *self = Self::Point(*v + rhs),
-            Self::Range(v) => *self = Self::Range(*v + rhs),
-        }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
-}
 
-impl MulAssign<usize> for Position {
-    fn mul_assign(&mut self, rhs: usize) {
-        match self {
-            Self::Point(v) => *self = Self::Point(*v * rhs),
-            Self::Range(v) => *self = Self::Range(*v * rhs),
-        }
-    }
-}
+    // Smallest span covering both spans, regardless of their order.
+    pub fn join(&self, other: &Span) -> Span {
+        let start = match self.start.offset <= other.start.offset {
+            true => self.start,
+            false => other.start,
+        };
+        let end = match self.end.offset >= other.end.offset {
+            true => self.end,
+            false => other.end,
+        };
 
-impl PartialOrd for Position {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.line().cmp(&other.line()))
+        Span::new(start, end)
     }
 }
 
-impl Display for Position {
+impl Display for Span {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Point(v) => write!(f, "{}", v),
-            Self::Range(v) => write!(f, "{}", v),
-        }
+        write!(f, "{} -> {}", self.start, self.end)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::position::Position;
-
-    use super::{Point, Range};
-
-    #[test]
-    fn point() {
-        let mut p = Point::default() + 1;
-
-        // Add
-        assert_eq!(0, p.line(), "line remains during add");
-        assert_eq!(1, p.char(), "char increase during add");
-
-        // Mul
-        p = p * 2;
-        assert_eq!(1, p.line(), "line increase during mul");
-        assert_eq!(2, p.char(), "char set during mul");
-
-        // AddAssign
-        p += 1;
-        assert_eq!(1, p.line(), "line remains during add assign");
-        assert_eq!(3, p.char(), "char increase during add assign");
-
-        // MulAssign
-        p *= 2;
-        assert_eq!(2, p.line(), "line increase during mul assign");
-        assert_eq!(2, p.char(), "char set during mul assign");
-
-        // Display
-        assert_eq!("(L2,C2)", format!("{}", p))
-    }
-
-    #[test]
-    fn range() {
-        let mut r = Range::default() + 1;
-
-        // Add
-        assert_eq!(0, r.line(), "line remains during add");
-        assert_eq!(1, r.char(), "char increase during add");
-
-        // Mul
-        r = r * 2;
-        assert_eq!(1, r.line(), "line increase during mul");
-        assert_eq!(2, r.char(), "char set during mul");
-
-        // AddAssign
-        r += 1;
-        assert_eq!(1, r.line(), "line remains during add assign");
-        assert_eq!(3, r.char(), "char increase during add assign");
-
-        // MulAssign
-        r *= 2;
-        assert_eq!(2, r.line(), "line increase during mul assign");
-        assert_eq!(2, r.char(), "char set during mul assign");
-
-        //Display
-        assert_eq!("(L0,C0) -> (L2,C2)", format!("{}", r))
-    }
+    use super::*;
 
     #[test]
-    fn point_position() {
-        let mut p = Position::from(Point::default());
-
-        // Add
-        p = p + 1;
-        assert_eq!(0, p.line(), "line remains during add");
-        assert_eq!(1, p.char(), "char increase during add");
-
-        // Mul
-        p = p * 2;
-        assert_eq!(1, p.line(), "line increase during mul");
-        assert_eq!(2, p.char(), "char set during mul");
-
-        // AddAssign
-        p += 1;
-        assert_eq!(1, p.line(), "line remains during add assign");
-        assert_eq!(3, p.char(), "char increase during add assign");
-
-        // MulAssign
-        p *= 2;
-        assert_eq!(2, p.line(), "line increase during mul assign");
-        assert_eq!(2, p.char(), "char set during mul assign");
+    fn advance_ok() {
+        let mut loc = Location::default();
+        for byte in "é\nab".bytes() {
+            loc.advance(byte);
+        }
 
-        //Display
-        assert_eq!("(L2,C2)", format!("{}", p))
+        assert_eq!(loc, Location::new(5, 2, 3));
     }
 
     #[test]
-    fn range_position() {
-        let mut p = Position::from(Range::default());
-
-        // Add
-        p = p + 1;
-        assert_eq!(0, p.line(), "line remains during add");
-        assert_eq!(1, p.char(), "char increase during add");
-
-        // Mul
-        p = p * 2;
-        assert_eq!(1, p.line(), "line increase during mul");
-        assert_eq!(2, p.char(), "char set during mul");
-
-        // AddAssign
-        p += 1;
-        assert_eq!(1, p.line(), "line remains during add assign");
-        assert_eq!(3, p.char(), "char increase during add assign");
-
-        // MulAssign
-        p *= 2;
-        assert_eq!(2, p.line(), "line increase during mul assign");
-        assert_eq!(2, p.char(), "char set during mul assign");
-
-        //Display
-        assert_eq!("(L0,C0) -> (L2,C2)", format!("{}", p))
+    fn join_ok() {
+        let a = Span::new(Location::new(4, 1, 5), Location::new(7, 1, 8));
+        let b = Span::new(Location::new(10, 2, 1), Location::new(12, 2, 3));
+
+        assert_eq!(a.join(&b), b.join(&a));
+        assert_eq!(a.join(&b).start(), a.start());
+        assert_eq!(a.join(&b).end(), b.end());
+        assert_eq!(a.join(&b).len(), 8);
+        assert_eq!(format!("{}", a.join(&b)), "1:5 -> 2:3");
     }
 }
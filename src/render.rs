@@ -0,0 +1,375 @@
+use std::fmt::Write;
+
+use crate::diagnostic::Diagnostic;
+use crate::error::{LexerError, ParserError, ProtobufError};
+use crate::position::Span;
+
+// Renderer
+//
+// Formats errors the way rustc does: the message and error code, the location, the offending
+// source lines with the span underlined and finally notes and help text. Colors are ANSI escape
+// codes and are off by default e.g. when writing to a file.
+//
+// error[E0005]: expected field assignment('='), found '1'
+//  --> foo.proto:3:11
+//   |
+// 3 |   int32 a 1;
+//   |           ^
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+// Underlined span with an optional label, the primary one points at the error itself
+struct Label {
+    span: Span,
+    primary: bool,
+    text: String,
+}
+
+pub struct Renderer<'a> {
+    name: &'a str,
+    source: &'a str,
+    color: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(name: &'a str, source: &'a str) -> Self {
+        Self {
+            name,
+            source,
+            color: false,
+        }
+    }
+
+    pub fn set_color(&mut self, color: bool) {
+        self.color = color;
+    }
+
+    pub fn render(&self, error: &ParserError) -> String {
+        // The lexer error wrapper only adds noise to the message
+        let message = match error {
+            ParserError::Lexer(e) => e.to_string(),
+            e => e.to_string(),
+        };
+
+        self.emit(error.code(), &message, labels(error), notes(error))
+    }
+
+    // Any error returned by the crate. Errors of a file loaded by name e.g. with `load_file` are
+    // rendered against the source they hold rather than the one of the renderer.
+    pub fn render_error(&self, error: &ProtobufError) -> String {
+        match error {
+            ProtobufError::Parser(e) => self.render(e),
+            ProtobufError::Lexer(e) => {
+                self.emit(e.code(), &e.to_string(), primary(e.span()), lexer_notes(e))
+            }
+            ProtobufError::File { name, text, error } => {
+                let mut renderer = Renderer::new(name, text);
+                renderer.set_color(self.color);
+                renderer.render_error(error)
+            }
+            ProtobufError::IO(e) => self.emit(error.code(), &e.to_string(), vec![], read_notes()),
+            e => self.emit(e.code(), &e.to_string(), vec![], vec![]),
+        }
+    }
+
+    fn emit(
+        &self,
+        code: &str,
+        message: &str,
+        labels: Vec<Label>,
+        notes: Vec<(&'static str, String)>,
+    ) -> String {
+        let mut out = String::new();
+
+        let header = format!("error[{code}]");
+        let _ = writeln!(
+            out,
+            "{}: {}",
+            self.paint(RED, &header),
+            self.paint(BOLD, message)
+        );
+
+        let width = labels
+            .iter()
+            .map(|l| l.span.start().line().to_string().len())
+            .max()
+            .unwrap_or(0);
+        let pad = " ".repeat(width);
+        let gutter = self.paint(BLUE, "|");
+
+        let location = match labels.iter().find(|l| l.primary) {
+            Some(label) => format!("{}:{}", self.name, label.span.start()),
+            None => self.name.to_string(),
+        };
+        let _ = writeln!(out, "{pad}{} {location}", self.paint(BLUE, "-->"));
+
+        if !labels.is_empty() {
+            let _ = writeln!(out, "{pad} {gutter}");
+        }
+
+        let mut prev_line: Option<usize> = None;
+        for label in &labels {
+            let line = label.span.start().line();
+            let text = self.source.lines().nth(line - 1).unwrap_or_default();
+
+            if prev_line.is_some_and(|prev| line > prev + 1) {
+                let _ = writeln!(out, "{}", self.paint(BLUE, "..."));
+            }
+            if prev_line != Some(line) {
+                let number = self.paint(BLUE, &format!("{line:>width$}"));
+                let _ = writeln!(out, "{number} {gutter} {text}");
+            }
+            prev_line = Some(line);
+
+            let (color, mark) = match label.primary {
+                true => (RED, "^"),
+                false => (BLUE, "-"),
+            };
+            let underline = format!(
+                "{} {}",
+                mark.repeat(underline_len(label.span, text)),
+                label.text
+            );
+            let _ = writeln!(
+                out,
+                "{pad} {gutter} {}{}",
+                indentation(label.span, text),
+                self.paint(color, underline.trim_end())
+            );
+        }
+
+        if !notes.is_empty() && !labels.is_empty() {
+            let _ = writeln!(out, "{pad} {gutter}");
+        }
+        for (kind, text) in notes {
+            let _ = writeln!(
+                out,
+                "{pad} {} {text}",
+                self.paint(BOLD, &format!("= {kind}:"))
+            );
+        }
+
+        out
+    }
+
+    // Every diagnostic separated by a blank line, as printed by rustc.
+    pub fn render_all(&self, diagnostics: &[Diagnostic]) -> String {
+        diagnostics
+            .iter()
+            .map(|d| self.render(d.error()))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        match self.color {
+            true => format!("{color}{text}{RESET}"),
+            false => text.to_string(),
+        }
+    }
+}
+
+// Spans to underline in source order, errors without a location e.g. a failed read have none.
+fn labels(error: &ParserError) -> Vec<Label> {
+    let span = match error.span() {
+        Some(span) => span,
+        None => return vec![],
+    };
+
+    let mut res = vec![];
+    if let ParserError::DuplicateDefinition { name, previous, .. } = error {
+        res.push(Label {
+            span: *previous,
+            primary: false,
+            text: format!("'{name}' first defined here"),
+        });
+        res.push(Label {
+            span,
+            primary: true,
+            text: "defined again here".to_string(),
+        });
+    } else {
+        res.extend(primary(Some(span)));
+    }
+
+    res.sort_by_key(|l| l.span.start().offset());
+    res
+}
+
+fn primary(span: Option<Span>) -> Vec<Label> {
+    span.map(|span| Label {
+        span,
+        primary: true,
+        text: String::new(),
+    })
+    .into_iter()
+    .collect()
+}
+
+fn notes(error: &ParserError) -> Vec<(&'static str, String)> {
+    match error {
+        ParserError::UnexpectedEof { .. } => vec![(
+            "help",
+            "check for a missing semicolon(';') or closing brace('}')".to_string(),
+        )],
+        ParserError::DuplicateDefinition { .. } => vec![(
+            "help",
+            "names must be unique within a scope, rename or remove one of the definitions"
+                .to_string(),
+        )],
+        ParserError::Lexer(e) => lexer_notes(e),
+        _ => vec![],
+    }
+}
+
+fn lexer_notes(error: &LexerError) -> Vec<(&'static str, String)> {
+    match error {
+        LexerError::InvalidEncoding { .. } => {
+            vec![(
+                "note",
+                "protobuf files must be encoded as UTF-8".to_string(),
+            )]
+        }
+        LexerError::Buffer(_) => read_notes(),
+        _ => vec![],
+    }
+}
+
+fn read_notes() -> Vec<(&'static str, String)> {
+    vec![("note", "the source could not be read".to_string())]
+}
+
+// Whitespace up to the start of the span, tabs are kept so the underline lines up.
+fn indentation(span: Span, text: &str) -> String {
+    text.chars()
+        .take(span.start().column() - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect()
+}
+
+// Spans over several lines are underlined up to the end of the first one, empty spans e.g. the
+// end of the file get a single mark.
+fn underline_len(span: Span, text: &str) -> usize {
+    let (start, end) = (span.start(), span.end());
+
+    let len = match end.line() == start.line() {
+        true => end.column().saturating_sub(start.column()),
+        false => text.chars().count().saturating_sub(start.column() - 1),
+    };
+
+    len.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cst::Cst;
+    use crate::types::proto::Proto;
+
+    use super::*;
+
+    fn diagnostics(source: &str) -> Vec<Diagnostic> {
+        let mut tokens = Cst::try_from(source).unwrap().token_stream();
+        let _ = Proto::from(&mut tokens);
+        tokens.take_diagnostics()
+    }
+
+    #[test]
+    fn render_ok() {
+        let source = "message A {\n  int32 a 1;\n}\n";
+        let diagnostics = diagnostics(source);
+
+        let renderer = Renderer::new("a.proto", source);
+        assert_eq!(
+            renderer.render(diagnostics[0].error()),
+            "error[E0005]: expected field assignment('='), found '1'
+ --> a.proto:2:11
+  |
+2 |   int32 a 1;
+  |           ^
+"
+        );
+    }
+
+    #[test]
+    fn render_duplicate_ok() {
+        let source = "enum E {\n  X = 0;\n\n  X = 1;\n}\n";
+        let diagnostics = diagnostics(source);
+
+        let renderer = Renderer::new("e.proto", source);
+        assert_eq!(
+            renderer.render_all(&diagnostics),
+            "error[E0007]: 'X' is defined more than once
+ --> e.proto:4:3
+  |
+2 |   X = 0;
+  |   ------ 'X' first defined here
+...
+4 |   X = 1;
+  |   ^^^^^^ defined again here
+  |
+  = help: names must be unique within a scope, rename or remove one of the definitions
+"
+        );
+    }
+
+    #[test]
+    fn render_color_ok() {
+        let source = "syntax = \"proto3\"";
+        let diagnostics = diagnostics(source);
+
+        let mut renderer = Renderer::new("s.proto", source);
+        renderer.set_color(true);
+        let out = renderer.render(diagnostics[0].error());
+
+        assert!(
+            out.starts_with("\x1b[1;31merror[E0006]\x1b[0m: "),
+            "{out:?}"
+        );
+        assert!(out.contains("\x1b[1;31m^\x1b[0m"), "{out:?}");
+        assert!(out.contains("= help:"), "{out:?}");
+
+        renderer.set_color(false);
+        assert!(!renderer.render(diagnostics[0].error()).contains('\x1b'));
+    }
+
+    #[test]
+    fn render_error_ok() {
+        let source = "syntax = \"proto3\";\nmessage A {\n  int32 a = 1 $;\n}\n";
+        let error = crate::parse_str("a.proto", source).unwrap_err();
+
+        let renderer = Renderer::new("a.proto", source);
+        assert_eq!(
+            renderer.render_error(&error),
+            "error[E0003]: invalid token '$', unexpected character
+ --> a.proto:3:15
+  |
+3 |   int32 a = 1 $;
+  |               ^
+"
+        );
+
+        // File errors are rendered against their own source
+        let source = "message B { int32 b = 0; }";
+        let error = ProtobufError::File {
+            name: "b.proto".to_string(),
+            text: source.to_string(),
+            error: Box::new(crate::parse_str("b.proto", source).unwrap_err()),
+        };
+        let out = renderer.render_error(&error);
+        assert!(out.starts_with("error[E0004]: invalid value '0'"), "{out}");
+        assert!(out.contains(" --> b.proto:1:23\n"), "{out}");
+        assert!(out.ends_with("1 | message B { int32 b = 0; }\n  |                       ^\n"));
+
+        let io = ProtobufError::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(
+            renderer.render_error(&io),
+            "error[E0001]: entity not found
+--> a.proto
+ = note: the source could not be read
+"
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::error::ProtobufError;
+
+// Source resolver
+//
+// Finds the source of a file from its canonical import path e.g. `foo/bar.proto`. Resolvers not
+// having the file return `None` so the next one in a chain can be tried. Sources are returned as
+// bytes, invalid UTF-8 is reported by the parser with its location.
+
+pub trait SourceResolver: Send + Sync {
+    fn resolve(&self, path: &str) -> Result<Option<Vec<u8>>, ProtobufError>;
+}
+
+// Files in memory keyed by import path
+impl SourceResolver for HashMap<String, String> {
+    fn resolve(&self, path: &str) -> Result<Option<Vec<u8>>, ProtobufError> {
+        Ok(self.get(path).map(|v| v.as_bytes().to_vec()))
+    }
+}
+
+// Raw files in memory keyed by import path, like the entries of an archive
+impl SourceResolver for HashMap<String, Vec<u8>> {
+    fn resolve(&self, path: &str) -> Result<Option<Vec<u8>>, ProtobufError> {
+        Ok(self.get(path).cloned())
+    }
+}
+
+// Include root on disk, like `protoc -I`
+pub struct DirectoryResolver {
+    root: PathBuf,
+}
+
+impl DirectoryResolver {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl SourceResolver for DirectoryResolver {
+    fn resolve(&self, path: &str) -> Result<Option<Vec<u8>>, ProtobufError> {
+        let file = self.root.join(path);
+
+        match file.is_file() {
+            true => Ok(Some(std::fs::read(file)?)),
+            false => Ok(None),
+        }
+    }
+}
+
+// Tries each resolver in order, the first one having the file wins.
+#[derive(Default)]
+pub struct ChainedResolver {
+    resolvers: Vec<Box<dyn SourceResolver>>,
+}
+
+impl ChainedResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, resolver: impl SourceResolver + 'static) {
+        self.resolvers.push(Box::new(resolver));
+    }
+}
+
+impl SourceResolver for ChainedResolver {
+    fn resolve(&self, path: &str) -> Result<Option<Vec<u8>>, ProtobufError> {
+        for resolver in &self.resolvers {
+            if let Some(source) = resolver.resolve(path)? {
+                return Ok(Some(source));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+// Archives are read once up front, only `.proto` entries are kept.
+#[cfg(any(feature = "tar", feature = "zip"))]
+fn archive_entry(
+    files: &mut HashMap<String, Vec<u8>>,
+    name: &str,
+    mut entry: impl std::io::Read,
+) -> Result<(), ProtobufError> {
+    if !name.ends_with(".proto") {
+        return Ok(());
+    }
+
+    let mut source = vec![];
+    std::io::Read::read_to_end(&mut entry, &mut source)?;
+    files.insert(crate::workspace::canonical(name)?, source);
+
+    Ok(())
+}
+
+#[cfg(feature = "tar")]
+pub struct TarResolver {
+    files: HashMap<String, Vec<u8>>,
+}
+
+#[cfg(feature = "tar")]
+impl TarResolver {
+    // Uncompressed tar archive e.g. wrap the reader in a gzip decoder for `.tar.gz` bundles.
+    pub fn new(reader: impl std::io::Read) -> Result<Self, ProtobufError> {
+        let mut files = HashMap::new();
+        let mut archive = tar::Archive::new(reader);
+
+        for entry in archive.entries()? {
+            let entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let name = entry.path()?.to_string_lossy().to_string();
+            archive_entry(&mut files, &name, entry)?;
+        }
+
+        Ok(Self { files })
+    }
+}
+
+#[cfg(feature = "tar")]
+impl SourceResolver for TarResolver {
+    fn resolve(&self, path: &str) -> Result<Option<Vec<u8>>, ProtobufError> {
+        self.files.resolve(path)
+    }
+}
+
+#[cfg(feature = "zip")]
+pub struct ZipResolver {
+    files: HashMap<String, Vec<u8>>,
+}
+
+#[cfg(feature = "zip")]
+impl ZipResolver {
+    pub fn new(reader: impl std::io::Read + std::io::Seek) -> Result<Self, ProtobufError> {
+        let mut files = HashMap::new();
+        let mut archive = zip::ZipArchive::new(reader).map_err(std::io::Error::other)?;
+
+        for i in 0..archive.len() {
+            let entry = archive.by_index(i).map_err(std::io::Error::other)?;
+            if !entry.is_file() {
+                continue;
+            }
+
+            let name = entry.name().to_string();
+            archive_entry(&mut files, &name, entry)?;
+        }
+
+        Ok(Self { files })
+    }
+}
+
+#[cfg(feature = "zip")]
+impl SourceResolver for ZipResolver {
+    fn resolve(&self, path: &str) -> Result<Option<Vec<u8>>, ProtobufError> {
+        self.files.resolve(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(files: &[(&str, &str)]) -> HashMap<String, String> {
+        files
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn chained_ok() {
+        let mut chain = ChainedResolver::new();
+        chain.push(files(&[("a.proto", "first")]));
+        chain.push(files(&[("a.proto", "second"), ("b.proto", "b")]));
+
+        assert_eq!(
+            chain.resolve("a.proto").unwrap().as_deref(),
+            Some(&b"first"[..])
+        );
+        assert_eq!(
+            chain.resolve("b.proto").unwrap().as_deref(),
+            Some(&b"b"[..])
+        );
+        assert_eq!(chain.resolve("c.proto").unwrap(), None);
+    }
+
+    #[test]
+    fn memory_ok() {
+        let mut workspace = crate::workspace::Workspace::new();
+        workspace.push_resolver(files(&[
+            ("a.proto", "import \"b.proto\";\nmessage A { B b = 1; }"),
+            ("b.proto", "message B {}"),
+        ]));
+
+        workspace.load("a.proto").unwrap();
+        assert_eq!(workspace.files().len(), 2);
+        assert_eq!(workspace.graph().importers("b.proto"), vec!["a.proto"]);
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn tar_ok() {
+        let mut builder = tar::Builder::new(vec![]);
+        for (path, source) in [("./foo/a.proto", "message A {}"), ("README", "readme")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(source.len() as u64);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, source.as_bytes())
+                .unwrap();
+        }
+        let archive = builder.into_inner().unwrap();
+
+        let resolver = TarResolver::new(archive.as_slice()).unwrap();
+        assert_eq!(
+            resolver.resolve("foo/a.proto").unwrap().as_deref(),
+            Some(&b"message A {}"[..])
+        );
+        assert_eq!(resolver.resolve("README").unwrap(), None);
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn zip_ok() {
+        use std::io::{Cursor, Write};
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+        let options = zip::write::SimpleFileOptions::default();
+        writer.add_directory("foo/", options).unwrap();
+        writer.start_file("foo/a.proto", options).unwrap();
+        writer.write_all(b"message A {}").unwrap();
+        let archive = writer.finish().unwrap();
+
+        let resolver = ZipResolver::new(archive).unwrap();
+        assert_eq!(
+            resolver.resolve("foo/a.proto").unwrap().as_deref(),
+            Some(&b"message A {}"[..])
+        );
+        assert_eq!(resolver.resolve("foo").unwrap(), None);
+
+        assert!(ZipResolver::new(Cursor::new(b"not a zip".to_vec())).is_err());
+    }
+}
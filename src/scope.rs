@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use crate::error::ParserError;
+use crate::position::Span;
+
+// Scope
+//
+// Names defined in a single scope e.g. the fields and nested types of a message, defining a name
+// a second time is an error pointing at both definitions.
+
+#[derive(Debug, Default)]
+pub struct Scope {
+    names: HashMap<String, Span>,
+}
+
+impl Scope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn define(&mut self, name: &str, span: Span) -> Result<(), ParserError> {
+        match self.names.get(name) {
+            Some(previous) => Err(ParserError::DuplicateDefinition {
+                name: name.to_string(),
+                span,
+                previous: *previous,
+            }),
+            None => {
+                self.names.insert(name.to_string(), span);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::position::Location;
+
+    use super::*;
+
+    #[test]
+    fn define_ok() {
+        let first = Span::new(Location::new(0, 1, 1), Location::new(3, 1, 4));
+        let second = Span::new(Location::new(4, 2, 1), Location::new(7, 2, 4));
+
+        let mut scope = Scope::new();
+        assert!(scope.define("foo", first).is_ok());
+        assert!(scope.define("bar", second).is_ok());
+
+        match scope.define("foo", second) {
+            Err(e @ ParserError::DuplicateDefinition { previous, .. }) => {
+                assert_eq!(previous, first);
+                assert_eq!(e.span(), Some(second));
+                assert_eq!(e.code(), "E0007");
+            }
+            res => panic!("expected a duplicate definition, got {res:?}"),
+        }
+    }
+}
@@ -1,97 +1,86 @@
-use std::{fmt::Display, ops::Deref};
+use std::{fmt::Display, iter::Peekable, ops::Deref, str::Chars};
 
 use crate::{
-    error::ParserError,
-    position::{Point, Position},
+    error::{LexerError, ParserError},
+    position::Span,
 };
 
-const OPTION_NAME_URL: &str = "https://protobuf.com/docs/language-spec#option-names";
 const IDENT_URL: &str = "https://protobuf.dev/reference/protobuf/proto3-spec/#identifiers";
 const CONST_URL: &str = "https://protobuf.dev/reference/protobuf/proto3-spec/#constant";
+const INT_LIT_URL: &str = "https://protobuf.com/docs/language-spec#numeric-literals";
+const STR_LIT_URL: &str = "https://protobuf.com/docs/language-spec#string-literals";
 
 #[derive(Clone, Debug)]
 pub struct Token {
     typ: Type,
-    pos: Position,
+    span: Span,
 }
 
 impl Token {
-    pub fn new(typ: Type, pos: Position) -> Self {
-        Self { typ, pos }
+    pub fn new(typ: Type, span: Span) -> Self {
+        Self { typ, span }
     }
 
     pub fn illegal() -> Self {
         Self {
             typ: Type::Illegal,
-            pos: Position::default(),
+            span: Span::default(),
         }
     }
 
     pub fn bool(v: bool) -> Self {
         Self {
             typ: Type::from(v),
-            pos: Position::default(),
+            span: Span::default(),
         }
     }
 
-    pub fn i32(v: i32) -> Self {
+    pub fn int(v: u64) -> Self {
         Self {
             typ: Type::IntLit(v),
-            pos: Position::default(),
+            span: Span::default(),
         }
     }
 
-    pub fn constant(v: String) -> Self {
+    pub fn float(v: f64) -> Self {
         Self {
-            typ: Type::Constant(v),
-            pos: Position::default(),
+            typ: Type::FloatLit(v),
+            span: Span::default(),
         }
     }
 
-    pub fn ident(v: String) -> Self {
+    pub fn string(v: StringLit) -> Self {
         Self {
-            typ: Type::Ident(v),
-            pos: Position::default(),
+            typ: Type::StringLit(v),
+            span: Span::default(),
         }
     }
 
-    pub fn full_ident(v: String) -> Self {
+    pub fn ident(v: String) -> Self {
         Self {
-            typ: Type::FullIdent(v),
-            pos: Position::default(),
+            typ: Type::Ident(v),
+            span: Span::default(),
         }
     }
 
-    pub fn option_name(v: String) -> Self {
+    pub fn full_ident(v: String) -> Self {
         Self {
-            typ: Type::OptionName(v),
-            pos: Position::default(),
+            typ: Type::FullIdent(v),
+            span: Span::default(),
         }
     }
 
-    pub fn as_const(&self) -> Result<String, ParserError> {
-        let s = match &self.typ {
-            Type::Ident(v) => v,
-            Type::FullIdent(v) => v,
-            Type::Constant(v) => v,
-            invalid => {
-                return Err(ParserError::Syntax(
-                    format!("constant, check {CONST_URL} for more info"),
-                    format!("{invalid}"),
-                ))
-            }
-        };
-
-        Ok(s.to_string())
-    }
-
+    // Keywords are context-sensitive and valid identifiers e.g. a field named `stream`.
     pub fn as_ident(&self) -> Result<String, ParserError> {
         let s = match &self.typ {
             Type::Ident(v) => v,
-            invalid => {
-                return Err(ParserError::Syntax(
+            keyword if keyword.is_keyword() => return Ok(format!("{keyword}")),
+            // `true` and `false` are always lexed as literals but name fields and enum values too
+            Type::BoolLit(v) => return Ok(format!("{v}")),
+            _ => {
+                return Err(ParserError::unexpected(
                     format!("Ident, check {IDENT_URL} for more info"),
-                    format!("{invalid}"),
+                    self,
                 ))
             }
         };
@@ -103,10 +92,13 @@ impl Token {
         let s = match &self.typ {
             Type::Ident(v) => v,
             Type::FullIdent(v) => v,
-            invalid => {
-                return Err(ParserError::Syntax(
+            keyword if keyword.is_keyword() => return Ok(format!("{keyword}")),
+            // `true` and `false` are always lexed as literals but name fields and enum values too
+            Type::BoolLit(v) => return Ok(format!("{v}")),
+            _ => {
+                return Err(ParserError::unexpected(
                     format!("FullIdent, check {IDENT_URL} for more info"),
-                    format!("{invalid}"),
+                    self,
                 ))
             }
         };
@@ -114,28 +106,12 @@ impl Token {
         Ok(s.to_string())
     }
 
-    pub fn as_option_name(&self) -> Result<String, ParserError> {
-        let s = match &self.typ {
-            Type::Ident(v) => v,
-            Type::FullIdent(v) => v,
-            Type::OptionName(v) => v,
-            invalid => {
-                return Err(ParserError::Syntax(
-                    format!("OptionName, check {OPTION_NAME_URL} for more info"),
-                    format!("{invalid}"),
-                ))
-            }
-        };
-
-        Ok(s.to_string())
+    pub fn span(&self) -> Span {
+        self.span
     }
 
-    pub fn position(&self) -> &Position {
-        &self.pos
-    }
-
-    pub fn set_position(&mut self, pos: Position) {
-        self.pos = pos;
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
     }
 
     pub fn typ(&self) -> &Type {
@@ -153,7 +129,7 @@ impl Deref for Token {
 
 impl Display for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Token({}, {})", self.typ, self.pos)
+        write!(f, "Token({}, {})", self.typ, self.span)
     }
 }
 
@@ -161,7 +137,7 @@ impl From<Type> for Token {
     fn from(value: Type) -> Self {
         Self {
             typ: value,
-            pos: Position::from(Point::new(0, 0)),
+            span: Span::default(),
         }
     }
 }
@@ -177,24 +153,33 @@ impl PartialEq for Token {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     Illegal,
-    EOF,
+    Eof,
 
     // Identifiers and literals
     BoolLit(bool),
-    IntLit(i32),
-    Constant(String),
+    // Integer literals are unsigned, the sign is a separate token
+    IntLit(u64),
+    FloatLit(f64),
+    StringLit(StringLit),
     Ident(String),
     FullIdent(String),
-    OptionName(String),
+
+    // Comments hold their text without the delimiters
+    LineComment(String),
+    BlockComment(String),
 
     // Operators
     Assign,
+    Minus,
+    Plus,
 
     // Delimiters
+    Colon,
     Comma,
     Semicolon,
     LParen,
     RParen,
+    Dot,
     LBrace,
     RBrace,
     LBrack,
@@ -205,7 +190,11 @@ pub enum Type {
     Asterisk,
 
     // Keywords
+    Edition,
     Enum,
+    Extend,
+    Extensions,
+    Group,
     Import,
     Map,
     Message,
@@ -215,37 +204,78 @@ pub enum Type {
     Package,
     Public,
     Repeated,
+    Required,
     Reserved,
     Returns,
-    RPC,
+    Rpc,
     Service,
     Stream,
     Syntax,
     Weak,
 }
 
+impl Type {
+    pub fn is_keyword(&self) -> bool {
+        matches!(
+            self,
+            Self::Edition
+                | Self::Enum
+                | Self::Extend
+                | Self::Extensions
+                | Self::Group
+                | Self::Import
+                | Self::Map
+                | Self::Message
+                | Self::Oneof
+                | Self::Option
+                | Self::Optional
+                | Self::Package
+                | Self::Public
+                | Self::Repeated
+                | Self::Required
+                | Self::Reserved
+                | Self::Returns
+                | Self::Rpc
+                | Self::Service
+                | Self::Stream
+                | Self::Syntax
+                | Self::Weak
+        )
+    }
+}
+
 impl From<bool> for Type {
     fn from(value: bool) -> Self {
         Self::BoolLit(value)
     }
 }
 
-impl From<i32> for Type {
-    fn from(value: i32) -> Self {
+impl From<u64> for Type {
+    fn from(value: u64) -> Self {
         Self::IntLit(value)
     }
 }
 
+impl From<f64> for Type {
+    fn from(value: f64) -> Self {
+        Self::FloatLit(value)
+    }
+}
+
 impl From<&char> for Type {
     fn from(value: &char) -> Self {
         match value {
             '=' => Type::Assign,
+            '-' => Type::Minus,
+            '+' => Type::Plus,
+            ':' => Type::Colon,
             ',' => Type::Comma,
             ';' => Type::Semicolon,
             '*' => Type::Asterisk,
             '/' => Type::Slash,
             '(' => Type::LParen,
             ')' => Type::RParen,
+            '.' => Type::Dot,
             '{' => Type::LBrace,
             '}' => Type::RBrace,
             '[' => Type::LBrack,
@@ -261,7 +291,11 @@ impl From<&str> for Type {
     fn from(value: &str) -> Self {
         match value {
             // Keywords
+            "edition" => Type::Edition,
             "enum" => Type::Enum,
+            "extend" => Type::Extend,
+            "extensions" => Type::Extensions,
+            "group" => Type::Group,
             "import" => Type::Import,
             "map" => Type::Map,
             "message" => Type::Message,
@@ -271,9 +305,10 @@ impl From<&str> for Type {
             "package" => Type::Package,
             "public" => Type::Public,
             "repeated" => Type::Repeated,
+            "required" => Type::Required,
             "reserved" => Type::Reserved,
             "returns" => Type::Returns,
-            "rpc" => Type::RPC,
+            "rpc" => Type::Rpc,
             "service" => Type::Service,
             "stream" => Type::Stream,
             "syntax" => Type::Syntax,
@@ -287,24 +322,32 @@ impl Display for Type {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
             Self::Illegal => "".to_string(),
-            Self::EOF => "".to_string(),
+            Self::Eof => "".to_string(),
 
             // Identifiers and literals
             Self::BoolLit(v) => format!("{v}"),
             Self::IntLit(v) => format!("{v}"),
-            Self::Constant(v) => format!("{v}"),
-            Self::Ident(v) => format!("{v}"),
-            Self::FullIdent(v) => format!("{v}"),
-            Self::OptionName(v) => format!("{v}"),
+            Self::FloatLit(v) => format!("{}", Constant::Float(*v)),
+            Self::StringLit(v) => format!("{v}"),
+            Self::Ident(v) => v.to_string(),
+            Self::FullIdent(v) => v.to_string(),
+
+            // Comments
+            Self::LineComment(v) => format!("//{v}"),
+            Self::BlockComment(v) => format!("/*{v}*/"),
 
             // Operators
             Self::Assign => "=".to_string(),
+            Self::Minus => "-".to_string(),
+            Self::Plus => "+".to_string(),
 
             // Delimiters
+            Self::Colon => ":".to_string(),
             Self::Comma => ",".to_string(),
             Self::Semicolon => ";".to_string(),
             Self::LParen => "(".to_string(),
             Self::RParen => ")".to_string(),
+            Self::Dot => ".".to_string(),
             Self::LBrace => "{".to_string(),
             Self::RBrace => "}".to_string(),
             Self::LBrack => "[".to_string(),
@@ -315,7 +358,11 @@ impl Display for Type {
             Self::Asterisk => "*".to_string(),
 
             // Keywords
+            Self::Edition => "edition".to_string(),
             Self::Enum => "enum".to_string(),
+            Self::Extend => "extend".to_string(),
+            Self::Extensions => "extensions".to_string(),
+            Self::Group => "group".to_string(),
             Self::Import => "import".to_string(),
             Self::Map => "map".to_string(),
             Self::Message => "message".to_string(),
@@ -325,9 +372,10 @@ impl Display for Type {
             Self::Package => "package".to_string(),
             Self::Public => "public".to_string(),
             Self::Repeated => "repeated".to_string(),
+            Self::Required => "required".to_string(),
             Self::Reserved => "reserved".to_string(),
             Self::Returns => "returns".to_string(),
-            Self::RPC => "rpc".to_string(),
+            Self::Rpc => "rpc".to_string(),
             Self::Service => "service".to_string(),
             Self::Stream => "stream".to_string(),
             Self::Syntax => "syntax".to_string(),
@@ -337,3 +385,466 @@ impl Display for Type {
         write!(f, "{s}")
     }
 }
+
+// Constant
+//
+// Typed value of a constant e.g. an option value. Integers without a sign are kept unsigned so
+// the full uint64 range is representable, negative integers are kept signed.
+// https://protobuf.com/docs/language-spec#option-values
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constant {
+    Int(i128),
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+    String(StringLit),
+    Ident(String),
+}
+
+impl Constant {
+    // Parses a decimal, octal (`017`) or hexadecimal (`0x1F`) integer literal.
+    pub fn parse_int(value: &str) -> Option<u64> {
+        let (digits, radix) = match value.strip_prefix("0x").or(value.strip_prefix("0X")) {
+            Some(v) => (v, 16),
+            None if value.len() > 1 && value.starts_with('0') => (&value[1..], 8),
+            None => (value, 10),
+        };
+
+        if digits.is_empty() || !digits.chars().all(|ch| ch.is_digit(radix)) {
+            return None;
+        }
+
+        u64::from_str_radix(digits, radix).ok()
+    }
+
+    // Parses a floating point literal e.g. `1.5`, `.5`, `1.` or `1e10`. `inf` and `nan` are
+    // identifiers and only become floats when used as a constant.
+    pub fn parse_float(value: &str) -> Option<f64> {
+        let is_float = value.starts_with(|ch: char| ch.is_ascii_digit() || ch == '.')
+            && value.contains(['.', 'e', 'E'])
+            && value
+                .chars()
+                .all(|ch| ch.is_ascii_digit() || matches!(ch, '.' | 'e' | 'E' | '+' | '-'));
+
+        match is_float {
+            true => value.parse::<f64>().ok(),
+            false => None,
+        }
+    }
+
+    // Applies a leading `-` sign, only numbers can be negated.
+    pub fn negate(self) -> Result<Self, ParserError> {
+        match self {
+            Self::UInt(v) => Ok(Self::Int(-(v as i128))),
+            Self::Float(v) => Ok(Self::Float(-v)),
+            invalid => Err(ParserError::invalid(
+                format!("numeric constant after '-', check {CONST_URL} for more info"),
+                format!("{invalid}"),
+            )),
+        }
+    }
+
+    // Returns the value as a signed integer if it's an integer constant.
+    pub fn as_i128(&self) -> Option<i128> {
+        match self {
+            Self::Int(v) => Some(*v),
+            Self::UInt(v) => Some(*v as i128),
+            _ => None,
+        }
+    }
+
+    pub fn as_ident(&self) -> Option<&str> {
+        match self {
+            Self::Ident(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    // Checks an integer constant is within `min..=max` e.g. field numbers or enum values.
+    pub fn as_int_in(&self, min: i128, max: i128, expect_msg: &str) -> Result<i128, ParserError> {
+        match self.as_i128() {
+            Some(v) if (min..=max).contains(&v) => Ok(v),
+            _ => Err(ParserError::invalid(
+                format!("{expect_msg} in range {min} to {max}, check {INT_LIT_URL} for more info"),
+                format!("{self}"),
+            )),
+        }
+    }
+}
+
+impl TryFrom<&Token> for Constant {
+    type Error = ParserError;
+
+    fn try_from(token: &Token) -> Result<Self, Self::Error> {
+        match token.typ() {
+            Type::BoolLit(v) => Ok(Self::Bool(*v)),
+            Type::IntLit(v) => Ok(Self::UInt(*v)),
+            Type::FloatLit(v) => Ok(Self::Float(*v)),
+            Type::StringLit(v) => Ok(Self::String(v.clone())),
+            Type::Ident(v) | Type::FullIdent(v) => Ok(Self::from(v.as_str())),
+            _ => Err(ParserError::unexpected(
+                format!("constant, check {CONST_URL} for more info"),
+                token,
+            )),
+        }
+    }
+}
+
+impl From<&str> for Constant {
+    fn from(value: &str) -> Self {
+        if let Ok(v) = StringLit::try_from(value) {
+            return Self::String(v);
+        }
+
+        match value {
+            "true" => return Self::Bool(true),
+            "false" => return Self::Bool(false),
+            "inf" => return Self::Float(f64::INFINITY),
+            "nan" => return Self::Float(f64::NAN),
+            _ => (),
+        }
+
+        if let Some(v) = Self::parse_int(value) {
+            return Self::UInt(v);
+        }
+
+        if let Some(v) = Self::parse_float(value) {
+            return Self::Float(v);
+        }
+
+        Self::Ident(value.to_string())
+    }
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(v) => write!(f, "{v}"),
+            Self::UInt(v) => write!(f, "{v}"),
+            Self::Float(v) if v.is_nan() => write!(f, "nan"),
+            Self::Float(v) if v.is_infinite() => match v.is_sign_negative() {
+                true => write!(f, "-inf"),
+                false => write!(f, "inf"),
+            },
+            // Debug formatting keeps the fraction e.g. `1.0` instead of `1`
+            Self::Float(v) => write!(f, "{v:?}"),
+            Self::Bool(v) => write!(f, "{v}"),
+            Self::String(v) => write!(f, "{v}"),
+            Self::Ident(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+// StringLit
+//
+// String literal decoded to raw bytes, the original spelling is kept so the literal can be
+// printed as written e.g. `'caf\xc3\xa9' "s"`. Adjacent literals are concatenated.
+// https://protobuf.com/docs/language-spec#string-literals
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StringLit {
+    value: Vec<u8>,
+    raw: String,
+}
+
+impl StringLit {
+    // Creates a literal from its decoded value, the spelling is a double quoted escaped string.
+    pub fn new(value: Vec<u8>) -> Self {
+        let mut raw = String::from("\"");
+        for byte in &value {
+            match byte {
+                b'"' => raw.push_str("\\\""),
+                b'\\' => raw.push_str("\\\\"),
+                b'\n' => raw.push_str("\\n"),
+                0x20..=0x7e => raw.push(*byte as char),
+                _ => raw.push_str(&format!("\\x{byte:02x}")),
+            }
+        }
+        raw.push('"');
+
+        Self { value, raw }
+    }
+
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    // Returns the decoded value if it's valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        std::str::from_utf8(&self.value).ok()
+    }
+
+    // Appends an adjacent literal e.g. `"foo" "bar"` becomes `foobar`.
+    pub fn concat(&mut self, other: &StringLit) {
+        self.value.extend_from_slice(&other.value);
+        self.raw.push(' ');
+        self.raw.push_str(&other.raw);
+    }
+
+    // Checks if `value` is a single literal closed by an unescaped quote matching the opening one.
+    pub fn is_complete(value: &str) -> bool {
+        let quote = match value.chars().next() {
+            Some(ch @ ('"' | '\'')) => ch,
+            _ => return false,
+        };
+
+        let inner = &value[1..];
+        if !inner.ends_with(quote) {
+            return false;
+        }
+
+        let escapes = inner[..inner.len() - 1]
+            .chars()
+            .rev()
+            .take_while(|ch| *ch == '\\')
+            .count();
+
+        escapes % 2 == 0
+    }
+
+    // Pops between `min` and `max` digits of the given radix.
+    fn digits(chars: &mut Peekable<Chars>, radix: u32, min: usize, max: usize) -> Option<u32> {
+        let mut digits = String::new();
+
+        while digits.len() < max {
+            match chars.next_if(|ch| ch.is_digit(radix)) {
+                Some(ch) => digits.push(ch),
+                None => break,
+            }
+        }
+
+        if digits.len() < min {
+            return None;
+        }
+
+        u32::from_str_radix(&digits, radix).ok()
+    }
+
+    // Decodes the escape sequence following a backslash.
+    fn escape(chars: &mut Peekable<Chars>, value: &mut Vec<u8>) -> Option<()> {
+        let byte = match chars.peek()? {
+            '0'..='7' => u8::try_from(Self::digits(chars, 8, 1, 3)?).ok()?,
+            'x' | 'X' => {
+                chars.next();
+                Self::digits(chars, 16, 1, 2)? as u8
+            }
+            'u' | 'U' => {
+                // Unicode escapes take exactly 4 or 8 hex digits
+                let len = match chars.next()? {
+                    'u' => 4,
+                    _ => 8,
+                };
+
+                let ch = char::from_u32(Self::digits(chars, 16, len, len)?)?;
+                let mut buf = [0; 4];
+                value.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+
+                return Some(());
+            }
+            _ => match chars.next()? {
+                'a' => 0x07,
+                'b' => 0x08,
+                'f' => 0x0c,
+                'n' => b'\n',
+                'r' => b'\r',
+                't' => b'\t',
+                'v' => 0x0b,
+                '\\' => b'\\',
+                '\'' => b'\'',
+                '"' => b'"',
+                '?' => b'?',
+                _ => return None,
+            },
+        };
+
+        value.push(byte);
+
+        Some(())
+    }
+}
+
+impl TryFrom<&str> for StringLit {
+    type Error = LexerError;
+
+    // Decodes a single literal including its quotes e.g. `"café"`.
+    fn try_from(raw: &str) -> Result<Self, Self::Error> {
+        // The span is set by the caller which knows where the literal is
+        let invalid = |reason: &str| LexerError::InvalidLiteral {
+            literal: raw.to_string(),
+            reason: format!("{reason}, check {STR_LIT_URL} for more info"),
+            span: None,
+        };
+
+        if !Self::is_complete(raw) {
+            return Err(invalid("unterminated string literal"));
+        }
+
+        let mut value = vec![];
+        let mut chars = raw[1..raw.len() - 1].chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => Self::escape(&mut chars, &mut value)
+                    .ok_or_else(|| invalid("invalid escape sequence"))?,
+                '\n' | '\0' => return Err(invalid("invalid character in string literal")),
+                ch => {
+                    let mut buf = [0; 4];
+                    value.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                }
+            }
+        }
+
+        Ok(Self {
+            value,
+            raw: raw.to_string(),
+        })
+    }
+}
+
+impl Display for StringLit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_int_ok() {
+        assert_eq!(Constant::parse_int("0"), Some(0));
+        assert_eq!(Constant::parse_int("42"), Some(42));
+        assert_eq!(Constant::parse_int("017"), Some(15));
+        assert_eq!(Constant::parse_int("0x1F"), Some(31));
+        assert_eq!(Constant::parse_int("18446744073709551615"), Some(u64::MAX));
+
+        for invalid in ["", "09", "0x", "+1", "18446744073709551616", "1.5"] {
+            assert_eq!(Constant::parse_int(invalid), None, "accepted '{invalid}'");
+        }
+    }
+
+    #[test]
+    fn parse_float_ok() {
+        assert_eq!(Constant::parse_float("1.5"), Some(1.5));
+        assert_eq!(Constant::parse_float(".5"), Some(0.5));
+        assert_eq!(Constant::parse_float("1."), Some(1.0));
+        assert_eq!(Constant::parse_float("1e10"), Some(1e10));
+        assert_eq!(Constant::parse_float("2.5E-3"), Some(2.5e-3));
+
+        for invalid in ["1", "inf", "nan", "e10", "1.5f"] {
+            assert_eq!(Constant::parse_float(invalid), None, "accepted '{invalid}'");
+        }
+    }
+
+    #[test]
+    fn from_token_ok() {
+        let cases = [
+            (Type::IntLit(7), Constant::UInt(7)),
+            (Type::FloatLit(1e10), Constant::Float(1e10)),
+            (Type::BoolLit(true), Constant::Bool(true)),
+            (
+                Type::Ident("inf".to_string()),
+                Constant::Float(f64::INFINITY),
+            ),
+            (
+                Type::Ident("IMPLICIT".to_string()),
+                Constant::Ident("IMPLICIT".to_string()),
+            ),
+            (
+                Type::StringLit(StringLit::new(b"foo".to_vec())),
+                Constant::String(StringLit::new(b"foo".to_vec())),
+            ),
+        ];
+
+        for (typ, expected) in cases {
+            assert_eq!(Constant::try_from(&Token::from(typ)).unwrap(), expected);
+        }
+
+        let nan = Constant::try_from(&Token::from(Type::Ident("nan".to_string()))).unwrap();
+        assert_eq!(format!("{nan}"), "nan");
+    }
+
+    #[test]
+    fn negate_ok() {
+        assert_eq!(Constant::UInt(5).negate().unwrap(), Constant::Int(-5));
+        assert_eq!(
+            Constant::UInt(1 << 63).negate().unwrap().as_i128(),
+            Some(i64::MIN as i128)
+        );
+        assert_eq!(
+            format!("{}", Constant::Float(f64::INFINITY).negate().unwrap()),
+            "-inf"
+        );
+        assert!(Constant::Bool(true).negate().is_err());
+    }
+
+    #[test]
+    fn range_err() {
+        let max = 536_870_911;
+
+        assert!(Constant::UInt(max as u64)
+            .as_int_in(1, max, "field number")
+            .is_ok());
+        assert!(Constant::UInt(max as u64 + 1)
+            .as_int_in(1, max, "field number")
+            .is_err());
+        assert!(Constant::Int(-1).as_int_in(1, max, "field number").is_err());
+        assert!(Constant::Float(1.0)
+            .as_int_in(1, max, "field number")
+            .is_err());
+    }
+
+    #[test]
+    fn string_lit_ok() {
+        let cases: [(&str, &[u8]); 7] = [
+            (r#""foo""#, b"foo"),
+            (r"'a\'b'", b"a'b"),
+            (r#""\x41\101\n""#, b"AA\n"),
+            (r#""café""#, "caf\u{e9}".as_bytes()),
+            (r#""\U0001F600""#, "\u{1F600}".as_bytes()),
+            (r#""\u00e9f""#, "\u{e9}f".as_bytes()),
+            (r#""a // b; { }""#, b"a // b; { }"),
+        ];
+
+        for (raw, expected) in cases {
+            let lit = StringLit::try_from(raw).unwrap();
+            assert_eq!(lit.value(), expected, "decoding {raw}");
+            assert_eq!(format!("{lit}"), raw);
+        }
+    }
+
+    #[test]
+    fn string_lit_err() {
+        for raw in [
+            r#""foo"#,
+            r#""foo'"#,
+            r#""\q""#,
+            r#""\x""#,
+            "\"a\nb\"",
+            r#""\U00110000""#,
+            r#""\u00e""#,
+            r#""\U1F600""#,
+        ] {
+            assert!(StringLit::try_from(raw).is_err(), "accepted {raw}");
+        }
+    }
+
+    #[test]
+    fn string_lit_concat_ok() {
+        let mut lit = StringLit::try_from(r#""foo""#).unwrap();
+        lit.concat(&StringLit::try_from("'bar'").unwrap());
+
+        assert_eq!(lit.as_str(), Some("foobar"));
+        assert_eq!(format!("{lit}"), r#""foo" 'bar'"#);
+        assert_eq!(
+            format!("{}", StringLit::new(b"a\"\xff".to_vec())),
+            r#""a\"\xff""#
+        );
+    }
+}
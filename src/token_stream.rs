@@ -1,213 +1,249 @@
 use std::fmt::Display;
 use std::ops::Deref;
 
+use crate::diagnostic::Diagnostic;
 use crate::error::ParserError;
-use crate::token::{Token, Type};
+use crate::position::Span;
+use crate::token::{Constant, StringLit, Token, Type};
 
-#[derive(Debug, Clone, PartialEq)]
+// Token stream
+//
+// Tokens in source order together with a cursor. Parsers read forwards and look ahead with `peek`
+// and `peek_nth`, consumed tokens stay in place so the span of a node can be taken from its first
+// and last token. Errors recovered from are collected as diagnostics.
+
+#[derive(Debug)]
 pub struct TokenStream {
     inner: Vec<Token>,
+    pos: usize,
+    diagnostics: Vec<Diagnostic>,
+    // Whether the file declares an edition, some statements are written differently than with
+    // `syntax` e.g. reserved names are identifiers instead of string literals.
+    editions: bool,
 }
 
 impl TokenStream {
     pub fn new() -> TokenStream {
-        TokenStream { inner: vec![] }
+        TokenStream {
+            inner: vec![],
+            pos: 0,
+            diagnostics: vec![],
+            editions: false,
+        }
     }
 
     pub fn push(&mut self, token: Token) {
         self.inner.push(token)
     }
 
-    pub fn peek(&self) -> Option<&Token> {
-        self.inner.last()
+    pub fn is_editions(&self) -> bool {
+        self.editions
     }
 
-    pub fn pop(&mut self) -> Option<Token> {
-        self.inner.pop()
+    pub fn set_editions(&mut self, editions: bool) {
+        self.editions = editions;
     }
 
-    pub fn reverse(&mut self) {
-        self.inner.reverse()
-    }
+    // Pushes a token, adjacent string literals are concatenated into the previous literal.
+    pub fn push_concat(&mut self, token: Token) {
+        if let (Type::StringLit(v), Some(last)) = (token.typ(), self.inner.last_mut()) {
+            if let Type::StringLit(prev) = last.typ() {
+                let mut lit = prev.clone();
+                lit.concat(v);
 
-    pub fn is_empty(&self) -> bool {
-        self.inner.is_empty()
-    }
+                let mut concat = Token::string(lit);
+                concat.set_span(last.span().join(&token.span()));
+                *last = concat;
+                return;
+            }
+        }
 
-    pub fn set_inner(&mut self, inner: Vec<Token>) {
-        self.inner = inner;
+        self.inner.push(token)
     }
 
-    // Selections
+    pub fn peek(&self) -> Option<&Token> {
+        self.inner.get(self.pos)
+    }
 
-    pub fn select_until(&mut self, until: Type) -> TokenStream {
-        let mut selection = TokenStream::new();
+    // Looks `n` tokens past the next one, `peek_nth(0)` is the same as `peek`.
+    pub fn peek_nth(&self, n: usize) -> Option<&Token> {
+        self.inner.get(self.pos + n)
+    }
 
-        for token in &self.inner {
-            selection.push(token.to_owned());
+    pub fn pop(&mut self) -> Option<Token> {
+        let token = self.inner.get(self.pos).cloned();
 
-            if token.typ() == &until {
-                break;
-            }
+        if token.is_some() {
+            self.pos += 1;
         }
 
-        return selection;
+        token
     }
 
-    // Block swallows everything until the end token is found. It's designed to consume characters
-    // before the actual block begins to include tokens like block identifiers and block names e.g.
-    // `message MessageName { .. }`
-    pub fn select_block(&mut self, begin: Type, end: Type) -> TokenStream {
-        let mut selection = TokenStream::new();
-        let mut counter = 0;
-
-        for token in &self.inner {
-            selection.push(token.to_owned());
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.inner.len()
+    }
 
-            if token.typ() == &begin {
-                counter += 1;
-            }
+    // Span covering every token in the stream, consumed or not.
+    pub fn span(&self) -> Span {
+        match (self.inner.first(), self.inner.last()) {
+            (Some(first), Some(last)) => first.span().join(&last.span()),
+            _ => Span::default(),
+        }
+    }
 
-            if token.typ() == &end {
-                counter -= 1;
+    // Cursor position to pass to `span_from` once a node is parsed.
+    pub fn mark(&self) -> usize {
+        self.pos
+    }
 
-                if counter == 0 {
-                    break;
-                }
+    // Span from the token at `mark` up to and including the last consumed token.
+    pub fn span_from(&self, mark: usize) -> Span {
+        match (self.inner.get(mark), self.pos.checked_sub(1)) {
+            (Some(first), Some(last)) if last >= mark => {
+                first.span().join(&self.inner[last].span())
             }
+            _ => Span::default(),
         }
-
-        return selection;
     }
 
-    // Needs to be handled by token stream since we don't know how to make a selection until we
-    // reached the end.
-    pub fn select_block_comment(&mut self) -> Result<TokenStream, ParserError> {
-        let mut ts = TokenStream::new();
-
-        let open_slash = self.next_eq(Type::Slash, "block comment opening('/')")?;
-        let open_aster = self.next_eq(Type::Asterisk, "block comment opening('*')")?;
+    // Error recovery
 
-        if open_slash.position().line() != open_aster.position().line()
-            && (open_slash.position().char() + 1) != open_aster.position().char()
-        {
-            return Err(ParserError::Syntax(
-                "block comment opening(\"/*\")".to_string(),
-                format!("broken block comment opening near {open_aster}"),
-            ));
-        }
+    // Records `error` and skips the statement starting at `mark`. The statement ends after a
+    // semicolon or a block at the same depth, or right before the closing brace of the enclosing
+    // body which is left for the caller. At least one token is skipped.
+    pub fn recover(&mut self, mark: usize, error: ParserError) {
+        // Errors without a span of their own point at the last token read
+        let span = match self.pos.checked_sub(1) {
+            Some(last) if last >= mark => self.inner[last].span(),
+            _ => self.peek().map(|t| t.span()).unwrap_or_default(),
+        };
+        self.report(error.with_span(span));
 
-        ts.push(open_slash);
-        ts.push(open_aster);
+        self.pos = mark;
+        let mut depth = 0;
 
         while let Some(token) = self.pop() {
-            let is_aster = token.typ().eq(&Type::Asterisk);
-            let line = token.position().line();
-            let character = token.position().char();
-            ts.push(token);
-
-            // Check for ending
-            if is_aster {
-                if let Some(peek_token) = self.peek() {
-                    if peek_token.typ().eq(&Type::Slash)
-                        && line == peek_token.position().line()
-                        && (character + 1) == peek_token.position().char()
-                    {
-                        break;
+            match token.typ() {
+                Type::LBrace => depth += 1,
+                Type::RBrace if depth == 0 && self.pos - 1 > mark => {
+                    self.pos -= 1;
+                    return;
+                }
+                Type::RBrace if depth <= 1 => {
+                    // An option value e.g. `option (foo) = { .. };` still ends with a semicolon
+                    if self.peek_eq(Type::Semicolon) {
+                        self.pos += 1;
                     }
+                    return;
                 }
+                Type::RBrace => depth -= 1,
+                Type::Semicolon if depth == 0 => return,
+                _ => (),
             }
         }
+    }
 
-        // We already consumed the closing asterisk and only a slash should remain.
-        let close_slash = self.next_eq(Type::Slash, "block comment closing('/')")?;
-        ts.push(close_slash);
+    // Records `error` without skipping anything, for problems found in well formed statements
+    // e.g. a name defined twice.
+    pub fn report(&mut self, error: ParserError) {
+        let span = error.span().unwrap_or_default();
+        self.diagnostics.push(Diagnostic::new(error, span));
+    }
 
-        Ok(ts)
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
     }
 
-    pub fn select_line_comment(&mut self) -> Result<TokenStream, ParserError> {
-        let mut tokens = TokenStream::new();
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
 
-        let slash1 = self.next_eq(Type::Slash, "line comment first opening slash")?;
-        let slash2 = self.next_eq(Type::Slash, "line comment second opening slash")?;
+    // Parser utils
 
-        if slash1.position().line() != slash2.position().line()
-            || (slash1.position().char() + 1) != slash2.position().char()
-        {
-            return Err(ParserError::Syntax(
-                "line comment opening(\"//\")".to_string(),
-                format!("broken line comment opening near {slash2}"),
-            ));
+    pub fn peek_eq(&self, expect: Type) -> bool {
+        match self.peek() {
+            Some(token) => token.typ() == &expect,
+            None => false,
         }
+    }
 
-        let line_num = slash2.position().line();
-
-        tokens.push(slash1);
-        tokens.push(slash2);
+    // Checks the next tokens in order e.g. `[Type::Map, Type::LT]` for `map<`.
+    pub fn peeks_eq(&self, expect: &[Type]) -> bool {
+        expect
+            .iter()
+            .enumerate()
+            .all(|(i, typ)| self.peek_nth(i).is_some_and(|t| t.typ() == typ))
+    }
 
-        while let Some(peek_token) = self.peek() {
-            // Break if we found line ending
-            if peek_token.position().line() > line_num {
-                break;
-            }
+    // Groups are the only fields followed by a block e.g. `repeated group Result = 1 { .. }`,
+    // anything else starting with `group` is a field of a type named `group`. Comments in between
+    // are skipped.
+    pub fn is_group(&self) -> bool {
+        let mut types = self.inner[self.pos.min(self.inner.len())..]
+            .iter()
+            .map(|t| t.typ())
+            .filter(|t| !matches!(t, Type::LineComment(_) | Type::BlockComment(_)))
+            .peekable();
+
+        types.next_if(|t| matches!(t, Type::Optional | Type::Required | Type::Repeated));
+
+        let head = types.next() == Some(&Type::Group)
+            && matches!(types.next(), Some(Type::Ident(_)))
+            && types.next() == Some(&Type::Assign)
+            && matches!(types.next(), Some(Type::IntLit(_)));
+        if !head {
+            return false;
+        }
 
-            match self.pop() {
-                Some(v) => tokens.push(v),
-                None => {
-                    return Err(ParserError::Syntax(
-                        "line comment values".to_string(),
-                        "nothing".to_string(),
-                    ))
+        // Options come before the body e.g. `group Result = 1 [deprecated = true] { .. }`
+        if types.next_if(|t| *t == &Type::LBrack).is_some() {
+            let mut depth = 1;
+            while depth > 0 {
+                match types.next() {
+                    Some(Type::LBrack) => depth += 1,
+                    Some(Type::RBrack) => depth -= 1,
+                    Some(_) => (),
+                    None => return false,
                 }
             }
         }
 
-        Ok(tokens)
+        types.next() == Some(&Type::LBrace)
     }
 
-    // Parser utils
-
-    pub fn peek_eq(&self, expect: Type) -> bool {
-        let next = self.inner.last();
-
-        match next {
-            Some(token) => token.typ() == &expect,
-            None => return false,
+    // Pops the next token, running out of tokens is reported at the end of the last one.
+    pub fn next(&mut self, expect_msg: &str) -> Result<Token, ParserError> {
+        match self.pop() {
+            Some(token) => Ok(token),
+            None => Err(self.eof(expect_msg)),
         }
     }
 
-    pub fn peeks_eq(&self, expect: &[Type]) -> bool {
-        let needle: Vec<Token> = expect.iter().map(|typ| Token::from(typ.clone())).collect();
-        self.inner.ends_with(needle.as_slice())
-    }
+    pub fn eof(&self, expect_msg: &str) -> ParserError {
+        let span = match self.inner.last() {
+            Some(last) => Span::new(last.span().end(), last.span().end()),
+            None => Span::default(),
+        };
 
-    pub fn is_line_comment(&self) -> bool {
-        self.peeks_eq(&[Type::Slash, Type::Slash])
+        ParserError::eof(expect_msg, span)
     }
 
-    pub fn is_block_comment(&mut self) -> bool {
-        self.peeks_eq(&[Type::Slash, Type::Asterisk])
+    // Error for the next token not being what the grammar expects.
+    pub fn unexpected(&self, expect_msg: &str) -> ParserError {
+        match self.peek() {
+            Some(token) => ParserError::unexpected(expect_msg, token),
+            None => self.eof(expect_msg),
+        }
     }
 
     pub fn next_eq(&mut self, expect: Type, expect_msg: &str) -> Result<Token, ParserError> {
-        let token = match self.inner.pop() {
-            Some(v) => v,
-            None => {
-                return Err(ParserError::Syntax(
-                    expect_msg.to_string(),
-                    "nothing".to_string(),
-                ))
-            }
-        };
+        let token = self.next(expect_msg)?;
 
         if *token == expect {
-            return Ok(token);
+            Ok(token)
         } else {
-            return Err(ParserError::Syntax(
-                expect_msg.to_string(),
-                format!("{token}"),
-            ));
+            Err(ParserError::unexpected(expect_msg, &token))
         }
     }
 
@@ -216,109 +252,88 @@ impl TokenStream {
         expect: &[Type],
         expect_msg: &str,
     ) -> Result<Token, ParserError> {
-        let token = match self.inner.pop() {
-            Some(v) => v,
-            None => {
-                return Err(ParserError::Syntax(
-                    expect_msg.to_string(),
-                    "nothing".to_string(),
-                ))
-            }
-        };
+        let token = self.next(expect_msg)?;
 
         if expect.contains(&token) {
             Ok(token)
         } else {
-            Err(ParserError::Syntax(
-                expect_msg.to_string(),
-                format!("{token}"),
-            ))
+            Err(ParserError::unexpected(expect_msg, &token))
         }
     }
 
     // Convertions
 
-    pub fn intlit_as_i32(&mut self, expect_msg: &str) -> Result<i32, ParserError> {
-        let token = match self.inner.pop() {
-            Some(v) => v,
-            None => {
-                return Err(ParserError::Syntax(
-                    expect_msg.to_string(),
-                    "nothing".to_string(),
-                ))
-            }
+    // Pops a constant, an optional sign is located before the value e.g. `-1` or `-inf`.
+    pub fn constant_as_typed(&mut self, expect_msg: &str) -> Result<Constant, ParserError> {
+        let sign = match self.peek_eq(Type::Minus) || self.peek_eq(Type::Plus) {
+            true => self.pop(),
+            false => None,
         };
 
-        match token.typ() {
-            Type::IntLit(v) => Ok(*v),
-            invalid => Err(ParserError::Syntax(
-                format!("{expect_msg}(intLit)"),
-                format!("{invalid}"),
-            )),
-        }
-    }
+        let token = self.next(expect_msg)?;
+        let value = Constant::try_from(&token)?;
 
-    pub fn optname_as_string(&mut self, expect_msg: &str) -> Result<String, ParserError> {
-        let token = match self.inner.pop() {
-            Some(v) => v,
-            None => {
-                return Err(ParserError::Syntax(
-                    expect_msg.to_string(),
-                    "nothing".to_string(),
-                ))
-            }
+        let span = match &sign {
+            Some(sign) => sign.span().join(&token.span()),
+            None => token.span(),
+        };
+
+        let res = match sign.as_ref().map(|t| t.typ()) {
+            Some(Type::Minus) => value.negate(),
+            Some(Type::Plus) => match value {
+                Constant::UInt(_) | Constant::Float(_) => Ok(value),
+                invalid => Err(ParserError::invalid(
+                    "numeric constant after '+'",
+                    format!("{invalid}"),
+                )),
+            },
+            _ => Ok(value),
         };
 
-        token.as_option_name()
+        res.map_err(|e| e.with_span(span))
+    }
+
+    // Pops a string literal, quoted constants are accepted as well.
+    pub fn strlit(&mut self, expect_msg: &str) -> Result<StringLit, ParserError> {
+        let token = self.next(expect_msg)?;
+
+        match token.typ() {
+            Type::StringLit(v) => Ok(v.clone()),
+            _ => Err(ParserError::unexpected(
+                format!("{expect_msg}(strLit)"),
+                &token,
+            )),
+        }
     }
 
     pub fn ident_as_string(&mut self, expect_msg: &str) -> Result<String, ParserError> {
-        let token = match self.inner.pop() {
-            Some(v) => v,
-            None => {
-                return Err(ParserError::Syntax(
-                    expect_msg.to_string(),
-                    "nothing".to_string(),
-                ))
-            }
-        };
+        let token = self.next(expect_msg)?;
 
         token.as_ident()
     }
 
     pub fn fullident_as_string(&mut self, expect_msg: &str) -> Result<String, ParserError> {
-        let token = match self.inner.pop() {
-            Some(v) => v,
-            None => {
-                return Err(ParserError::Syntax(
-                    expect_msg.to_string(),
-                    "nothing".to_string(),
-                ))
-            }
-        };
+        let token = self.next(expect_msg)?;
 
         token.as_full_ident()
     }
+}
 
-    pub fn constant_as_string(&mut self, expect_msg: &str) -> Result<String, ParserError> {
-        let token = match self.inner.pop() {
-            Some(v) => v,
-            None => {
-                return Err(ParserError::Syntax(
-                    expect_msg.to_string(),
-                    "nothing".to_string(),
-                ))
-            }
-        };
-
-        token.as_const()
+impl From<Vec<Token>> for TokenStream {
+    fn from(inner: Vec<Token>) -> Self {
+        TokenStream {
+            inner,
+            pos: 0,
+            diagnostics: vec![],
+            editions: false,
+        }
     }
 }
 
 impl Display for TokenStream {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "TokenStream {{")?;
-        for token in &self.inner {
+        for token in &self.inner[self.pos.min(self.inner.len())..] {
             writeln!(f, "    {token}")?;
         }
         writeln!(f, "}}")
@@ -333,82 +348,27 @@ impl Deref for TokenStream {
     }
 }
 
+// Stream of the given token types without spans, for tests building token sequences by hand.
+#[cfg(test)]
+pub fn stream(types: &[Type]) -> TokenStream {
+    let tokens: Vec<Token> = types.iter().cloned().map(Token::from).collect();
+
+    TokenStream::from(tokens)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{
-        position::{Point, Position},
-        token::Token,
-    };
+    use crate::lexer::Lexer;
+    use crate::token::Token;
 
     use super::*;
 
-    fn types_to_tokens(types: &[Type]) -> Vec<Token> {
-        types
-            .iter()
-            .map(|typ| Token::from(typ.clone()))
-            .collect::<Vec<Token>>()
-    }
-
     fn line_stream() -> TokenStream {
-        let mut ts = TokenStream::new();
-
-        let _ = &[
+        stream(&[
             Type::Syntax,
             Type::Ident("foo".to_string()),
             Type::Semicolon,
-        ]
-        .iter()
-        .map(|t| Token::from(t.clone()))
-        .collect::<Vec<Token>>()
-        .iter()
-        .for_each(|t| ts.push(t.clone()));
-
-        return ts;
-    }
-
-    fn block_stream() -> TokenStream {
-        let mut ts = TokenStream::new();
-
-        let _ = &[
-            Type::Message,
-            Type::Ident("foo".to_string()),
-            Type::LBrace,
-            Type::Ident("bar".to_string()),
-            Type::RBrace,
-            Type::Package,
-        ]
-        .iter()
-        .map(|t| Token::from(t.clone()))
-        .collect::<Vec<Token>>()
-        .iter()
-        .for_each(|t| ts.push(t.clone()));
-
-        return ts;
-    }
-
-    fn nested_block_stream() -> TokenStream {
-        let mut ts = TokenStream::new();
-
-        let _ = &[
-            Type::Message,
-            Type::Ident("foo".to_string()),
-            Type::LBrace,
-            Type::Ident("bar".to_string()),
-            Type::Enum,
-            Type::Ident("baz".to_string()),
-            Type::LBrace,
-            Type::Ident("inner".to_string()),
-            Type::RBrace,
-            Type::RBrace,
-            Type::Package,
-        ]
-        .iter()
-        .map(|t| Token::from(t.clone()))
-        .collect::<Vec<Token>>()
-        .iter()
-        .for_each(|t| ts.push(t.clone()));
-
-        return ts;
+        ])
     }
 
     #[test]
@@ -429,206 +389,161 @@ mod tests {
         assert_eq!(expected, ts.inner);
     }
 
+    #[test]
+    fn push_concat_ok() {
+        let mut ts = TokenStream::new();
+
+        ts.push_concat(Token::from(Type::Assign));
+        for raw in [r#""foo""#, "'bar'", r#""\x21""#] {
+            ts.push_concat(Token::string(StringLit::try_from(raw).unwrap()));
+        }
+        ts.push_concat(Token::from(Type::Semicolon));
+
+        assert_eq!(ts.inner.len(), 3);
+        match ts.inner[1].typ() {
+            Type::StringLit(v) => {
+                assert_eq!(v.as_str(), Some("foobar!"));
+                assert_eq!(v.raw(), r#""foo" 'bar' "\x21""#);
+            }
+            invalid => panic!("expected string literal, got {invalid}"),
+        }
+    }
+
     #[test]
     fn peek_and_pop_and_is_empty_ok() {
         let mut ts = line_stream();
 
         let peek = ts.peek();
-        assert_eq!(Some(&Token::from(Type::Semicolon)), peek);
-        assert_eq!(ts.is_empty(), false);
+        assert_eq!(Some(&Token::from(Type::Syntax)), peek);
+        assert!(!ts.is_empty());
 
-        let _discarded = ts.pop();
+        assert_eq!(Some(Token::from(Type::Syntax)), ts.pop());
         let _discarded = ts.pop();
         let _discarded = ts.pop();
 
         let peek = ts.peek();
         assert_eq!(None, peek);
-        assert_eq!(ts.is_empty(), true);
-    }
+        assert_eq!(None, ts.pop());
+        assert!(ts.is_empty());
 
-    #[test]
-    fn reverse_ok() {
-        let mut ts = line_stream();
-        let expected = &[
-            Type::Semicolon,
-            Type::Ident("foo".to_string()),
-            Type::Syntax,
-        ]
-        .iter()
-        .map(|t| Token::from(t.clone()))
-        .collect::<Vec<Token>>();
-
-        ts.reverse();
-
-        assert_eq!(expected, &ts.inner);
+        // Consumed tokens are kept
+        assert_eq!(ts.len(), 3);
     }
 
     #[test]
-    fn select_until_ok() {
-        let mut ts = line_stream();
-
-        let expected = ts.clone();
-        let actual = ts.select_until(Type::Semicolon);
+    fn lookahead_ok() {
+        let mut ts = stream(&[
+            Type::Repeated,
+            Type::Group,
+            Type::Ident("Result".to_string()),
+            Type::Assign,
+            Type::IntLit(1),
+            Type::LBrace,
+        ]);
 
-        assert_eq!(expected, actual);
+        assert_eq!(ts.peek_nth(1), Some(&Token::from(Type::Group)));
+        assert_eq!(ts.peek_nth(6), None);
+        assert!(ts.peeks_eq(&[Type::Repeated, Type::Group]));
+        assert!(!ts.peeks_eq(&[Type::Group, Type::Repeated]));
+        assert!(ts.is_group());
+
+        ts.pop();
+        assert!(ts.is_group());
+        assert!(ts.peek_eq(Type::Group));
+
+        ts.pop();
+        assert!(!ts.is_group());
+        assert!(!ts.peeks_eq(&[Type::Ident("Result".to_string()), Type::Semicolon]));
+
+        // A field of a type named `group`
+        let ts = stream(&[
+            Type::Group,
+            Type::Ident("g".to_string()),
+            Type::Assign,
+            Type::IntLit(1),
+            Type::Semicolon,
+        ]);
+        assert!(!ts.is_group());
     }
 
     #[test]
-    fn select_block_ok() {
-        let mut ts = block_stream();
+    fn span_from_ok() {
+        let tokens = Lexer::new(b"syntax = \"proto3\";\npackage foo;")
+            .tokens()
+            .unwrap();
+        let mut ts = TokenStream::from(tokens);
 
-        let expected = &[
-            Type::Message,
-            Type::Ident("foo".to_string()),
-            Type::LBrace,
-            Type::Ident("bar".to_string()),
-            Type::RBrace,
-        ]
-        .iter()
-        .map(|t| Token::from(t.clone()))
-        .collect::<Vec<Token>>();
+        (0..4).for_each(|_| {
+            ts.pop();
+        });
 
-        let actual = ts.select_block(Type::LBrace, Type::RBrace);
+        let mark = ts.mark();
+        assert_eq!(ts.span_from(mark), Span::default());
 
-        assert_eq!(expected, &actual.inner);
+        (0..3).for_each(|_| {
+            ts.pop();
+        });
+        assert_eq!(format!("{}", ts.span_from(mark)), "2:1 -> 2:13");
+        assert_eq!(format!("{}", ts.span()), "1:1 -> 2:13");
     }
 
     #[test]
-    fn select_nested_block_ok() {
-        let mut ts = nested_block_stream();
+    fn recover_ok() {
+        let error = || ParserError::expected("test", "test");
 
-        let expected = &[
-            Type::Message,
-            Type::Ident("foo".to_string()),
+        // Skips up to and including the semicolon
+        let mut ts = stream(&[
+            Type::Ident("a".to_string()),
+            Type::Assign,
+            Type::Semicolon,
+            Type::Ident("b".to_string()),
+        ]);
+        ts.pop();
+        ts.pop();
+        ts.recover(0, error());
+        assert_eq!(ts.peek(), Some(&Token::from(Type::Ident("b".to_string()))));
+
+        // Skips a block and the semicolon of an option value, semicolons inside don't count
+        let mut ts = stream(&[
+            Type::Option,
             Type::LBrace,
-            Type::Ident("bar".to_string()),
-            Type::Enum,
-            Type::Ident("baz".to_string()),
+            Type::Semicolon,
             Type::LBrace,
-            Type::Ident("inner".to_string()),
             Type::RBrace,
             Type::RBrace,
-        ]
-        .iter()
-        .map(|t| Token::from(t.clone()))
-        .collect::<Vec<Token>>();
-
-        let actual = ts.select_block(Type::LBrace, Type::RBrace);
-
-        assert_eq!(expected, &actual.inner);
-    }
-
-    #[test]
-    fn select_block_comment() {
-        let mut ts = TokenStream::new();
-
-        // Add tokens for line 0
-        ts.set_inner(types_to_tokens(&[
-            Type::Slash,
-            Type::Asterisk,
-            Type::Ident("foo".to_string()),
-        ]));
-
-        // Add tokens for line 1
-        let _line1 = &[
-            Type::Asterisk,
-            Type::Ident("bar".to_string()),
-            Type::Slash,
-            Type::Asterisk,
-        ]
-        .iter()
-        .map(|t| {
-            let mut token = Token::from(t.clone());
-            token.set_position(Position::from(Point::new(1, 0)));
-            token
-        })
-        .collect::<Vec<Token>>()
-        .iter()
-        .for_each(|t| ts.push(t.clone()));
-
-        // The last slash needs to be located at the character after the closing asterisk.
-        let mut last = Token::from(Type::Slash);
-        last.set_position(Position::from(Point::new(1, 1)));
-        ts.push(last);
-
-        let expected = types_to_tokens(&[
-            Type::Slash,
-            Type::Asterisk,
-            Type::Ident("foo".to_string()),
-            Type::Asterisk,
-            Type::Ident("bar".to_string()),
-            Type::Slash,
-            Type::Asterisk,
-            Type::Slash,
+            Type::Semicolon,
+            Type::Ident("b".to_string()),
         ]);
+        ts.recover(0, error());
+        assert_eq!(ts.peek(), Some(&Token::from(Type::Ident("b".to_string()))));
 
-        ts.reverse();
-        let actual = ts.select_block_comment();
-        assert!(actual.is_ok());
-
-        let actual = actual.unwrap();
-        let it = expected.iter().zip(actual.inner.iter());
+        // Stops at the closing brace of the enclosing body
+        let mut ts = stream(&[Type::Ident("a".to_string()), Type::RBrace]);
+        ts.recover(0, error());
+        assert!(ts.peek_eq(Type::RBrace));
 
-        for (e, a) in it {
-            assert_eq!(e.typ(), a.typ());
-        }
+        // Always skips at least one token
+        ts.recover(1, error());
+        assert!(ts.is_empty());
+        assert_eq!(ts.diagnostics().len(), 2);
+        assert_eq!(ts.take_diagnostics().len(), 2);
+        assert!(ts.diagnostics().is_empty());
     }
 
     #[test]
-    fn select_line_comment() {
-        let mut ts = TokenStream::new();
-
-        // TODO Continue here
-        let slash1 = Token::from(Type::Slash);
-        let mut slash2 = Token::from(Type::Slash);
-
-        // Set correct position of slash2
-        slash2.set_position(Position::from(Point::new(0,1)));
-
-        ts.push(slash1);
-        ts.push(slash2);
-
-        let _ts_tokens = &[
-            Type::Ident("foo".to_string()),
-            Type::Slash,
-        ]
-        .iter()
-        .map(|t| Token::from(t.clone()))
-        .collect::<Vec<Token>>()
-        .iter()
-        .for_each(|t| ts.push(t.clone()));
-
-        // `last` should be excluded from the comment selection since it's located on another line
-        let mut last = Token::from(Type::Ident("bar".to_string()));
-        last.set_position(Position::from(Point::new(1, 0)));
-
-        ts.push(last);
-        ts.reverse();
-
-        let expected = types_to_tokens(&[
-            Type::Slash,
-            Type::Slash,
-            Type::Ident("foo".to_string()),
-            Type::Slash,
+    fn constant_as_typed_ok() {
+        let mut ts = stream(&[
+            Type::Minus,
+            Type::IntLit(5),
+            Type::Plus,
+            Type::Ident("inf".to_string()),
         ]);
 
-        let actual = ts.select_line_comment();
-
-        assert!(actual.is_ok());
-        assert_eq!(expected, actual.unwrap().inner);
-    }
-
-    //    pub fn peek_eq(&self, expect: Type) -> bool {
-    //    pub fn peeks_eq(&self, expect: &[Type]) -> bool {
-    //    pub fn is_line_comment(&self) -> bool {
-    //    pub fn is_block_comment(&mut self) -> bool {
-    //    pub fn next_eq(&mut self, expect: Type, expect_msg: &str) -> Result<Token, ParserError> {
-    //    pub fn next_contains(
-    //    pub fn intlit_as_i32(&mut self, expect_msg: &str) -> Result<i32, ParserError> {
-    //    pub fn optname_as_string(&mut self, expect_msg: &str) -> Result<String, ParserError> {
-    //    pub fn ident_as_string(&mut self, expect_msg: &str) -> Result<String, ParserError> {
-    //    pub fn fullident_as_string(&mut self, expect_msg: &str) -> Result<String, ParserError> {
-    //    pub fn constant_as_string(&mut self, expect_msg: &str) -> Result<String, ParserError> {
-    //    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    //    fn deref(&self) -> &Self::Target {
+        assert_eq!(ts.constant_as_typed("value").unwrap(), Constant::Int(-5));
+        assert!(matches!(
+            ts.constant_as_typed("value").unwrap(),
+            Constant::Float(v) if v.is_infinite()
+        ));
+        assert!(ts.constant_as_typed("value").is_err());
+    }
 }
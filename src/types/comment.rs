@@ -1,34 +1,55 @@
 use std::fmt::Display;
 
 use crate::{
+    error::ParserError,
     indent::{indent, level},
+    position::Span,
+    token::Type,
     token_stream::TokenStream,
 };
 
-pub trait Comment {}
-
 // Line comment
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct LineComment {
     inner: String,
+    span: Span,
 }
 
 impl LineComment {
     pub fn new(inner: String) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            span: Span::default(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
     }
 }
 
-impl From<TokenStream> for LineComment {
-    fn from(tokens: TokenStream) -> Self {
-        let inner: String = tokens
-            .iter()
-            .map(|t| format!("{}", t))
-            .collect::<Vec<String>>()
-            .join("");
+impl TryFrom<&mut TokenStream> for LineComment {
+    type Error = ParserError;
+
+    // Comments are single tokens holding their text without the delimiters
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        let start = tokens.mark();
+
+        let token = tokens.next("line comment")?;
+        let inner = match token.typ() {
+            Type::LineComment(v) => v.to_string(),
+            _ => return Err(ParserError::unexpected("line comment", &token)),
+        };
+
+        let mut res = Self::new(inner);
+        res.set_span(tokens.span_from(start));
 
-        Self::new(inner)
+        Ok(res)
     }
 }
 
@@ -44,38 +65,49 @@ impl Display for LineComment {
 #[derive(Clone, Debug, PartialEq)]
 pub struct BlockComment {
     inner: Vec<String>,
+    span: Span,
 }
 
 impl BlockComment {
     pub fn new(inner: Vec<String>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            span: Span::default(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
     }
 }
 
-impl From<TokenStream> for BlockComment {
-    fn from(mut tokens: TokenStream) -> Self {
-        // Discard opening and closing tokens
-        let _close_slash = tokens.pop();
-        let _close_aster = tokens.pop();
-        tokens.reverse();
-        let _open_slash = tokens.pop();
-        let open_aster = tokens.pop().unwrap();
-
-        let mut lines = vec![];
-        let mut line = vec![];
-        let mut line_num = open_aster.position().line();
-
-        while let Some(value) = tokens.pop() {
-            if value.position().line() != line_num {
-                lines.push(line.join(" "));
-                line.clear();
-                line_num = value.position().line();
-            }
-
-            line.push(format!("{}", value.typ()));
+impl TryFrom<&mut TokenStream> for BlockComment {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        let start = tokens.mark();
+
+        let token = tokens.next("block comment")?;
+        let text = match token.typ() {
+            Type::BlockComment(v) => v.to_string(),
+            _ => return Err(ParserError::unexpected("block comment", &token)),
+        };
+
+        // Blank lines around the text e.g. `/*\n foo\n */` are part of the delimiters
+        let mut lines: Vec<String> = text.lines().map(|l| l.trim().to_string()).collect();
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
         }
+        let begin = lines.iter().take_while(|l| l.is_empty()).count();
+
+        let mut res = Self::new(lines.split_off(begin));
+        res.set_span(tokens.span_from(start));
 
-        Self::new(lines)
+        Ok(res)
     }
 }
 
@@ -0,0 +1,111 @@
+use log::debug;
+use std::fmt::Display;
+
+use crate::error::ParserError;
+use crate::indent::indent;
+use crate::position::Span;
+use crate::token::{StringLit, Type};
+use crate::token_stream::TokenStream;
+
+// Editions with known feature defaults
+const EDITIONS: &[&str] = &["2023"];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Edition {
+    pub value: String,
+    // The literal as written e.g. `'2023'`, used when printing
+    pub raw: String,
+    pub span: Span,
+}
+
+impl Edition {
+    pub fn new(value: String) -> Self {
+        Edition {
+            raw: StringLit::new(value.as_bytes().to_vec()).raw().to_string(),
+            value,
+            span: Span::default(),
+        }
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn set_raw(&mut self, raw: String) {
+        self.raw = raw;
+    }
+}
+
+impl TryFrom<&mut TokenStream> for Edition {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("edition({:?})", tokens.peek());
+        let start = tokens.mark();
+
+        tokens.next_eq(Type::Edition, "edition identifier")?;
+        tokens.next_eq(Type::Assign, "edition assignment('=')")?;
+        let lit = tokens.strlit("edition value")?;
+        tokens.next_eq(Type::Semicolon, "edition line ending(';')")?;
+
+        // Either quote style is allowed, the value is kept without quotes and
+        // the literal as written
+        let value = match lit.as_str() {
+            Some(v) if EDITIONS.contains(&v) => v.to_string(),
+            _ => {
+                return Err(ParserError::invalid(
+                    format!("edition value to be one of \"{}\"", EDITIONS.join("\", \"")),
+                    lit.raw(),
+                ))
+            }
+        };
+
+        let mut res = Self::new(value);
+        res.set_raw(lit.raw().to_string());
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
+    }
+}
+
+impl Display for Edition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        indent(f)?;
+        write!(f, "edition = {};", self.raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::token::StringLit;
+    use crate::token_stream::stream;
+
+    use super::*;
+
+    #[test]
+    fn from_ok() {
+        let mut ts = stream(&[
+            Type::Edition,
+            Type::Assign,
+            Type::StringLit(StringLit::new(b"2023".to_vec())),
+            Type::Semicolon,
+        ]);
+
+        let res = Edition::try_from(&mut ts);
+        assert!(res.is_ok(), "edition parse error {:?}", res);
+
+        let edition = res.unwrap();
+        assert_eq!(format!("{edition}"), "edition = \"2023\";");
+    }
+
+    #[test]
+    fn unsupported_err() {
+        let tokens = Lexer::new(b"edition = \"2024\";").tokens().unwrap();
+
+        match Edition::try_from(&mut TokenStream::from(tokens)) {
+            Err(ParserError::InvalidLiteral { found, .. }) => assert_eq!(found, "\"2024\""),
+            res => panic!("expected an unsupported edition, got {res:?}"),
+        }
+    }
+}
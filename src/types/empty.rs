@@ -0,0 +1,49 @@
+use std::fmt::Display;
+
+use crate::error::ParserError;
+use crate::indent::indent;
+use crate::position::Span;
+use crate::token::Type;
+use crate::token_stream::TokenStream;
+
+// Empty statement e.g. the stray semicolon in `message Foo {};`
+//
+// Empty statements have no meaning, they are kept as members so a file can be printed back the way
+// it was written. Consumers not interested in them can simply skip the `Empty` members.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Empty {
+    pub span: Span,
+}
+
+impl Empty {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+}
+
+impl TryFrom<&mut TokenStream> for Empty {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        let start = tokens.mark();
+
+        tokens.next_eq(Type::Semicolon, "empty statement(';')")?;
+
+        let mut res = Self::new();
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
+    }
+}
+
+impl Display for Empty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        indent(f)?;
+        writeln!(f, ";")
+    }
+}
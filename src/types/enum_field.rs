@@ -1,8 +1,9 @@
-use std::fmt::Display;
 use log::debug;
+use std::fmt::Display;
 
 use crate::error::ParserError;
 use crate::indent::indent;
+use crate::position::Span;
 use crate::token::Type;
 use crate::token_stream::TokenStream;
 use crate::types::field_option::FieldOption;
@@ -12,6 +13,7 @@ pub struct EnumField {
     pub name: String,
     pub index: i32,
     pub options: Option<FieldOption>,
+    pub span: Span,
 }
 
 impl EnumField {
@@ -20,42 +22,49 @@ impl EnumField {
             name,
             index,
             options: None,
+            span: Span::default(),
         }
     }
 
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
     pub fn set_options(&mut self, options: Option<FieldOption>) {
         self.options = options;
     }
 }
 
-impl TryFrom<TokenStream> for EnumField {
+impl TryFrom<&mut TokenStream> for EnumField {
     type Error = ParserError;
 
-    // Parsing backwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("enum field({:?})", tokens);
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("enum field({:?})", tokens.peek());
+        let start = tokens.mark();
 
-        tokens.next_eq(Type::Semicolon, "enum field line ending(';')")?;
+        let name = tokens.ident_as_string("enum field name")?;
+        tokens.next_eq(Type::Assign, "enum field assignment('=')")?;
+
+        // Note index could be a negative integer according to spec.
+        // https://protobuf.dev/reference/protobuf/proto3-spec/#enum_definition
+        let index = tokens
+            .constant_as_typed("enum field index")?
+            .as_int_in(i32::MIN.into(), i32::MAX.into(), "enum field index")?;
 
         // Handle field options
-        let options = match tokens.peek_eq(Type::RBrack) {
-            true => {
-                let option_tokens = tokens.select_block(Type::RBrack, Type::LBrace);
-                Some(FieldOption::try_from(option_tokens)?)
-            }
+        let options = match tokens.peek_eq(Type::LBrack) {
+            true => Some(FieldOption::try_from(&mut *tokens)?),
             false => None,
         };
 
-        // Note index could be a negative integer according to spec.
-        // https://protobuf.dev/reference/protobuf/proto3-spec/#enum_definition
-        let index = tokens.intlit_as_i32("enum field index")?;
-        tokens.next_eq(Type::Assign, "enum field assignment('=')")?;
-        let name = tokens.ident_as_string("enum field name")?;
+        tokens.next_eq(Type::Semicolon, "enum field line ending(';')")?;
 
-        let mut res = Self::new(name, index);
+        let mut res = Self::new(name, index as i32);
         res.set_options(options);
 
-        return Ok(res);
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
     }
 }
 
@@ -69,3 +78,35 @@ impl Display for EnumField {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::token_stream::stream;
+
+    use super::*;
+
+    #[test]
+    fn negative_ok() {
+        let mut ts = stream(&[
+            Type::Ident("UNKNOWN".to_string()),
+            Type::Assign,
+            Type::Minus,
+            Type::IntLit(1 << 31),
+            Type::Semicolon,
+        ]);
+
+        let res = EnumField::try_from(&mut ts);
+        assert!(res.is_ok(), "enum field parse error {:?}", res);
+        assert_eq!(res.unwrap().index, i32::MIN);
+
+        // Without the sign the value no longer fits in an int32
+        let mut ts = stream(&[
+            Type::Ident("UNKNOWN".to_string()),
+            Type::Assign,
+            Type::IntLit(1 << 31),
+            Type::Semicolon,
+        ]);
+
+        assert!(EnumField::try_from(&mut ts).is_err());
+    }
+}
@@ -3,18 +3,30 @@ use std::fmt::Display;
 
 use crate::error::ParserError;
 use crate::indent::{indent, level};
+use crate::position::Span;
+use crate::scope::Scope;
 use crate::token::Type;
 use crate::token_stream::TokenStream;
+use crate::types::empty::Empty;
 use crate::types::comment::{BlockComment, LineComment};
 use crate::types::enum_field::EnumField;
 use crate::types::option_field::OptionField;
+use crate::types::reserved::Reserved;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum EnumMember {
     BlockComment(BlockComment),
+    Empty(Empty),
     Field(EnumField),
     LineComment(LineComment),
     Option(OptionField),
+    Reserved(Reserved),
+}
+
+impl From<Empty> for EnumMember {
+    fn from(value: Empty) -> Self {
+        Self::Empty(value)
+    }
 }
 
 impl From<BlockComment> for EnumMember {
@@ -41,9 +53,46 @@ impl From<OptionField> for EnumMember {
     }
 }
 
+impl From<Reserved> for EnumMember {
+    fn from(value: Reserved) -> Self {
+        Self::Reserved(value)
+    }
+}
+
+impl TryFrom<&mut TokenStream> for EnumMember {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        let typ = match tokens.peek() {
+            Some(token) => token.typ(),
+            None => return Err(tokens.eof("enum member")),
+        };
+
+        let member = match typ {
+            Type::Option => Self::from(OptionField::try_from(tokens)?),
+            Type::Reserved => Self::from(Reserved::try_from(tokens)?),
+            Type::LineComment(_) => Self::from(LineComment::try_from(tokens)?),
+            Type::BlockComment(_) => Self::from(BlockComment::try_from(tokens)?),
+            Type::Semicolon => Self::from(Empty::try_from(tokens)?),
+            _enum_field => Self::from(EnumField::try_from(tokens)?),
+        };
+
+        Ok(member)
+    }
+}
+
 impl Display for EnumMember {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self)
+        let i = level(f);
+
+        match self {
+            Self::BlockComment(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Empty(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Field(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::LineComment(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Option(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Reserved(v) => write!(f, "{:indent$}", v, indent = i),
+        }
     }
 }
 
@@ -51,6 +100,7 @@ impl Display for EnumMember {
 pub struct Enum {
     pub name: String,
     pub members: Vec<EnumMember>,
+    pub span: Span,
 }
 
 impl Enum {
@@ -58,74 +108,55 @@ impl Enum {
         Enum {
             name,
             members: vec![],
+            span: Span::default(),
         }
     }
 
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
     pub fn push(&mut self, member: EnumMember) {
         self.members.push(member);
     }
 }
 
-impl TryFrom<TokenStream> for Enum {
+impl TryFrom<&mut TokenStream> for Enum {
     type Error = ParserError;
 
-    // Parsing forwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("enum({:?})", tokens);
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("enum({:?})", tokens.peek());
+        let start = tokens.mark();
 
         tokens.next_eq(Type::Enum, "enum identifier")?;
         let name = tokens.ident_as_string("enum name")?;
         tokens.next_eq(Type::LBrace, "enum opening brace('{')")?;
 
         let mut enm = Enum::new(name);
+        let mut scope = Scope::new();
 
         // Handle enum body
-        while !tokens.is_empty() {
-            let peek_token = match tokens.peek() {
-                Some(v) => v,
-                None => {
-                    return Err(ParserError::Syntax(
-                        "enum member".to_string(),
-                        "nothing".to_string(),
-                    ));
-                }
-            };
-
-            let member = match peek_token.typ() {
-                Type::Option => {
-                    let line = tokens.select_until(Type::Semicolon);
-                    EnumMember::from(OptionField::try_from(line)?)
-                }
-                Type::Slash => {
-                    if tokens.is_line_comment() {
-                        let line = tokens.select_line_comment()?;
-                        EnumMember::from(LineComment::from(line))
-                    } else if tokens.is_block_comment() {
-                        let block = tokens.select_block_comment()?;
-                        EnumMember::from(BlockComment::from(block))
-                    } else {
-                        return Err(ParserError::Syntax(
-                            "enum comment".to_string(),
-                            format!("enum tokens: {tokens}"),
-                        ));
+        while tokens.peek().is_some_and(|t| t.typ() != &Type::RBrace) {
+            let start = tokens.mark();
+
+            match EnumMember::try_from(&mut *tokens) {
+                Ok(member) => {
+                    if let EnumMember::Field(v) = &member {
+                        if let Err(e) = scope.define(&v.name, v.span) {
+                            tokens.report(e);
+                        }
                     }
+                    enm.push(member)
                 }
-                Type::RBrace => {
-                    break;
-                }
-                _enum_field => {
-                    let line = tokens.select_until(Type::Semicolon);
-                    EnumMember::from(EnumField::try_from(line)?)
-                }
-            };
-
-            tokens.next_eq(Type::RBrace, "enum closing brace('}')")?;
-
-            enm.push(member);
+                Err(e) => tokens.recover(start, e),
+            }
         }
 
+        tokens.next_eq(Type::RBrace, "enum closing brace('}')")?;
+
+        enm.set_span(tokens.span_from(start));
 
-        return Ok(enm);
+        Ok(enm)
     }
 }
 
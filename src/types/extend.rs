@@ -0,0 +1,235 @@
+use log::debug;
+use std::fmt::Display;
+
+use crate::error::ParserError;
+use crate::indent::{indent, level};
+use crate::position::Span;
+use crate::token::Type;
+use crate::token_stream::TokenStream;
+use crate::types::comment::{BlockComment, LineComment};
+use crate::types::empty::Empty;
+use crate::types::field::Field;
+use crate::types::group::Group;
+
+// Extend e.g. `extend google.protobuf.FieldOptions { optional string foo = 50000; }`
+//
+// Proto2 extensions may be groups, their message type is declared in the scope enclosing the
+// extend block.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExtendMember {
+    BlockComment(BlockComment),
+    Empty(Empty),
+    Field(Field),
+    Group(Group),
+    LineComment(LineComment),
+}
+
+impl From<Empty> for ExtendMember {
+    fn from(value: Empty) -> Self {
+        Self::Empty(value)
+    }
+}
+
+impl From<BlockComment> for ExtendMember {
+    fn from(value: BlockComment) -> Self {
+        Self::BlockComment(value)
+    }
+}
+
+impl From<Field> for ExtendMember {
+    fn from(value: Field) -> Self {
+        Self::Field(value)
+    }
+}
+
+impl From<Group> for ExtendMember {
+    fn from(value: Group) -> Self {
+        Self::Group(value)
+    }
+}
+
+impl From<LineComment> for ExtendMember {
+    fn from(value: LineComment) -> Self {
+        Self::LineComment(value)
+    }
+}
+
+impl TryFrom<&mut TokenStream> for ExtendMember {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        let typ = match tokens.peek() {
+            Some(token) => token.typ(),
+            None => return Err(tokens.eof("extend member")),
+        };
+
+        let member = match typ {
+            Type::LineComment(_) => Self::from(LineComment::try_from(tokens)?),
+            Type::BlockComment(_) => Self::from(BlockComment::try_from(tokens)?),
+            Type::Semicolon => Self::from(Empty::try_from(tokens)?),
+            _ if tokens.is_group() => Self::from(Group::try_from(tokens)?),
+            _field => Self::from(Field::try_from(tokens)?),
+        };
+
+        Ok(member)
+    }
+}
+
+impl Display for ExtendMember {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let i = level(f);
+
+        match self {
+            Self::BlockComment(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Empty(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Field(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Group(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::LineComment(v) => write!(f, "{:indent$}", v, indent = i),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Extend {
+    pub extendee: String,
+    pub members: Vec<ExtendMember>,
+    pub span: Span,
+}
+
+impl Extend {
+    pub fn new(extendee: String) -> Self {
+        Extend {
+            extendee,
+            members: vec![],
+            span: Span::default(),
+        }
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn push(&mut self, member: ExtendMember) {
+        self.members.push(member);
+    }
+}
+
+impl TryFrom<&mut TokenStream> for Extend {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("extend({:?})", tokens.peek());
+        let start = tokens.mark();
+
+        tokens.next_eq(Type::Extend, "extend identifier")?;
+        let extendee = tokens.fullident_as_string("extend type")?;
+        tokens.next_eq(Type::LBrace, "extend opening brace('{')")?;
+
+        let mut res = Self::new(extendee);
+
+        while tokens.peek().is_some_and(|t| t.typ() != &Type::RBrace) {
+            let start = tokens.mark();
+
+            match ExtendMember::try_from(&mut *tokens) {
+                Ok(member) => res.push(member),
+                Err(e) => tokens.recover(start, e),
+            }
+        }
+
+        tokens.next_eq(Type::RBrace, "extend closing brace('}')")?;
+
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
+    }
+}
+
+impl Display for Extend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let i = level(f);
+        indent(f)?;
+
+        writeln!(f, "extend {} {{", self.extendee)?;
+        for member in &self.members {
+            writeln!(f, "{:indent$}", member, indent = i + 1)?;
+        }
+
+        indent(f)?;
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::types::field::Label;
+
+    use super::*;
+
+    fn extend(source: &str) -> (Result<Extend, ParserError>, TokenStream) {
+        let tokens = Lexer::new(source.as_bytes()).tokens().unwrap();
+        let mut ts = TokenStream::from(tokens);
+
+        (Extend::try_from(&mut ts), ts)
+    }
+
+    #[test]
+    fn fields_ok() {
+        let (res, _) = extend(
+            "extend google.protobuf.FieldOptions {\n  optional string foo = 50000 [deprecated = \
+             true];\n  ;\n  // bar\n  repeated int32 bar = 50001;\n}",
+        );
+        let extend = res.unwrap();
+
+        assert_eq!(extend.extendee, "google.protobuf.FieldOptions");
+        assert_eq!(extend.members.len(), 4);
+        match &extend.members[0] {
+            ExtendMember::Field(v) => {
+                assert_eq!(v.name, "foo");
+                assert_eq!(v.label, Label::Optional);
+                assert!(v.options.is_some());
+            }
+            invalid => panic!("expected a field, got {invalid:?}"),
+        }
+        match &extend.members[1] {
+            ExtendMember::Empty(v) => assert_eq!(format!("{}", v.span), "3:3 -> 3:4"),
+            invalid => panic!("expected an empty statement, got {invalid:?}"),
+        }
+        assert!(matches!(extend.members[2], ExtendMember::LineComment(_)));
+        let printed = format!("{extend}");
+        assert!(
+            printed.starts_with("extend google.protobuf.FieldOptions {\n"),
+            "{printed}"
+        );
+        assert!(printed.contains("\toptional string foo = 50000 [deprecated=true];\n"));
+    }
+
+    #[test]
+    fn group_ok() {
+        let (res, _) = extend("extend A { optional group G = 2 { optional int32 a = 1; } }");
+        let extend = res.unwrap();
+
+        match &extend.members[..] {
+            [ExtendMember::Group(v)] => {
+                assert_eq!(v.name(), "G");
+                assert_eq!(v.field.name, "g");
+                assert_eq!(v.field.index, 2);
+                assert_eq!(v.message.members.len(), 1);
+            }
+            invalid => panic!("expected a group, got {invalid:?}"),
+        }
+    }
+
+    #[test]
+    fn from_err() {
+        // Members failing to parse are reported and skipped
+        let (res, mut ts) = extend("extend A { int32 a 1; optional int32 b = 2; }");
+        assert_eq!(res.unwrap().members.len(), 1);
+        assert_eq!(ts.take_diagnostics().len(), 1);
+
+        for source in ["extend { int32 a = 1; }", "extend A { int32 a = 1;"] {
+            assert!(extend(source).0.is_err(), "accepted {source}");
+        }
+    }
+}
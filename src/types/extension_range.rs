@@ -0,0 +1,120 @@
+use log::debug;
+use std::fmt::Display;
+
+use crate::error::ParserError;
+use crate::indent::indent;
+use crate::position::Span;
+use crate::token::Type;
+use crate::token_stream::TokenStream;
+use crate::types::field_option::FieldOption;
+use crate::types::reserved::{NumberRange, Reserved};
+
+// ExtensionRange e.g. `extensions 100 to 199 [declaration = { number: 100 }];`
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtensionRange {
+    pub ranges: Vec<NumberRange>,
+    pub options: Option<FieldOption>,
+    pub span: Span,
+}
+
+impl ExtensionRange {
+    pub fn new(ranges: Vec<NumberRange>) -> Self {
+        ExtensionRange {
+            ranges,
+            options: None,
+            span: Span::default(),
+        }
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn set_options(&mut self, options: Option<FieldOption>) {
+        self.options = options;
+    }
+}
+
+impl TryFrom<&mut TokenStream> for ExtensionRange {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("extension range({:?})", tokens.peek());
+        let start = tokens.mark();
+
+        tokens.next_eq(Type::Extensions, "extensions identifier")?;
+        let ranges = Reserved::ranges_from(tokens)?;
+
+        let options = match tokens.peek_eq(Type::LBrack) {
+            true => Some(FieldOption::try_from(&mut *tokens)?),
+            false => None,
+        };
+
+        tokens.next_eq(Type::Semicolon, "extensions line ending(';')")?;
+
+        let mut res = Self::new(ranges);
+        res.set_options(options);
+
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
+    }
+}
+
+impl Display for ExtensionRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        indent(f)?;
+
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|range| format!("{range}"))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        match &self.options {
+            Some(v) => writeln!(f, "extensions {ranges} {v};"),
+            None => writeln!(f, "extensions {ranges};"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::token_stream::stream;
+    use crate::types::reserved::RangeEnd;
+
+    use super::*;
+
+    #[test]
+    fn from_ok() {
+        let mut ts = stream(&[
+            Type::Extensions,
+            Type::IntLit(100),
+            Type::Ident("to".to_string()),
+            Type::IntLit(199),
+            Type::Comma,
+            Type::IntLit(1000),
+            Type::Ident("to".to_string()),
+            Type::Ident("max".to_string()),
+            Type::Semicolon,
+        ]);
+
+        let res = ExtensionRange::try_from(&mut ts);
+        assert!(res.is_ok(), "extension range parse error {:?}", res);
+
+        let extensions = res.unwrap();
+        assert_eq!(
+            extensions.ranges,
+            vec![
+                NumberRange::new(100, Some(RangeEnd::Value(199))),
+                NumberRange::new(1000, Some(RangeEnd::Max)),
+            ]
+        );
+        assert_eq!(
+            format!("{extensions}"),
+            "extensions 100 to 199, 1000 to max;\n"
+        );
+    }
+}
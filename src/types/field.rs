@@ -3,63 +3,177 @@ use std::fmt::Display;
 use log::debug;
 
 use crate::error::ParserError;
+use crate::features::{FeatureSet, FieldPresence, RepeatedFieldEncoding};
 use crate::indent::indent;
-use crate::token::Type;
+use crate::position::Span;
+use crate::token::{Token, Type};
 use crate::token_stream::TokenStream;
 use crate::types::field_option::FieldOption;
+use crate::types::type_ref::{TypeKind, TypeRef, TypeRefKind};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Label {
+    #[default]
+    None,
+    Optional,
+    Required,
+    Repeated,
+}
+
+impl Label {
+    pub fn is_label(typ: &Type) -> bool {
+        matches!(typ, Type::Optional | Type::Required | Type::Repeated)
+    }
+}
+
+impl TryFrom<Token> for Label {
+    type Error = ParserError;
+
+    fn try_from(token: Token) -> Result<Self, Self::Error> {
+        match token.typ() {
+            Type::Optional => Ok(Label::Optional),
+            Type::Required => Ok(Label::Required),
+            Type::Repeated => Ok(Label::Repeated),
+            _ => Err(ParserError::unexpected(
+                "field label(optional, required, repeated)",
+                &token,
+            )),
+        }
+    }
+}
+
+impl Display for Label {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Self::None => "",
+            Self::Optional => "optional",
+            Self::Required => "required",
+            Self::Repeated => "repeated",
+        };
+        write!(f, "{label}")
+    }
+}
+
+const PRESENCE_URL: &str = "https://protobuf.dev/editions/features/#field_presence";
+
+// Field numbers are limited to 29 bits
+// https://protobuf.com/docs/language-spec#field-numbers
+pub const FIELD_NUMBER_MAX: u32 = 536_870_911;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Field {
     pub name: String,
-    pub typ: String,
-    pub index: i32,
+    pub typ: TypeRef,
+    pub index: u32,
     pub options: Option<FieldOption>,
-    pub repeated: bool,
+    pub label: Label,
+    pub span: Span,
 }
 
 impl Field {
-    pub fn new(name: String, typ: String, index: i32, repeated: bool) -> Self {
+    pub fn new(name: String, typ: TypeRef, index: u32, label: Label) -> Self {
         Field {
             name,
             typ,
             index,
             options: None,
-            repeated,
+            label,
+            span: Span::default(),
         }
     }
 
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
     pub fn set_options(&mut self, options: Option<FieldOption>) {
         self.options = options;
     }
+
+    pub fn is_repeated(&self) -> bool {
+        self.label == Label::Repeated
+    }
+
+    // Whether the field tracks being set given its resolved features. Repeated fields never do,
+    // message fields always do and other fields follow `field_presence`, which is explicit for
+    // oneof members and extensions. `kind` is what a named field type resolves to.
+    pub fn has_presence(&self, features: &FeatureSet, kind: TypeKind) -> bool {
+        match &self.typ.kind {
+            _ if self.is_repeated() => false,
+            TypeRefKind::Named { .. } if kind == TypeKind::Message => true,
+            _ => features.field_presence != FieldPresence::Implicit,
+        }
+    }
+
+    // Only repeated scalar numeric and enum fields can be packed.
+    pub fn is_packed(&self, features: &FeatureSet, kind: TypeKind) -> bool {
+        let packable = match &self.typ.kind {
+            TypeRefKind::Scalar(v) => v.is_packable(),
+            TypeRefKind::Named { .. } => kind == TypeKind::Enum,
+        };
+
+        self.is_repeated()
+            && packable
+            && features.repeated_field_encoding == RepeatedFieldEncoding::Packed
+    }
+
+    // Pops a field number and checks it's within `1..=FIELD_NUMBER_MAX`.
+    pub(crate) fn number_from(
+        tokens: &mut TokenStream,
+        expect_msg: &str,
+    ) -> Result<u32, ParserError> {
+        let value = tokens.constant_as_typed(expect_msg)?;
+        let number = value.as_int_in(1, FIELD_NUMBER_MAX as i128, expect_msg)?;
+
+        Ok(number as u32)
+    }
 }
 
-impl TryFrom<TokenStream> for Field {
+impl TryFrom<&mut TokenStream> for Field {
     type Error = ParserError;
 
-    // Parsing backwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("field({:?})", tokens);
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("field({:?})", tokens.peek());
+        let start = tokens.mark();
 
-        tokens.next_eq(Type::Semicolon, "field line ending(';')")?;
+        // Labels are valid type names, `optional foo = 1;` is a field of type `optional`
+        let mut label = Label::None;
+        if let Some(token) = tokens.peek() {
+            let is_type = tokens.peek_nth(2).is_some_and(|t| t.typ() == &Type::Assign);
+
+            if Label::is_label(token.typ()) && !is_type {
+                label = Label::try_from(token.clone())?;
 
-        let options = match tokens.peek_eq(Type::RBrack) {
-            true => {
-                let option_tokens = tokens.select_block(Type::RBrack, Type::LBrack);
-                Some(FieldOption::try_from(option_tokens)?)
+                // Presence is a feature in editions, only `repeated` is left
+                if tokens.is_editions() && label != Label::Repeated {
+                    return Err(ParserError::unexpected(
+                        format!("field label(repeated) in editions, check {PRESENCE_URL}"),
+                        token,
+                    ));
+                }
+
+                tokens.pop();
             }
+        }
+
+        let typ = TypeRef::type_from(tokens, "field type")?;
+        let name = tokens.ident_as_string("field name")?;
+        tokens.next_eq(Type::Assign, "field assignment('=')")?;
+        let index = Self::number_from(tokens, "field index")?;
+
+        let options = match tokens.peek_eq(Type::LBrack) {
+            true => Some(FieldOption::try_from(&mut *tokens)?),
             false => None,
         };
 
-        let index = tokens.intlit_as_i32("field index")?;
-        tokens.next_eq(Type::Assign, "field assignment('=')")?;
-        let name = tokens.ident_as_string("field name")?;
-        let typ = tokens.fullident_as_string("field value")?;
-        let repeated = tokens.peek_eq(Type::Repeated);
+        tokens.next_eq(Type::Semicolon, "field line ending(';')")?;
 
-        let mut res = Self::new(name, typ, index, repeated);
+        let mut res = Self::new(name, typ, index, label);
         res.set_options(options);
 
-        return Ok(res);
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
     }
 }
 
@@ -67,8 +181,8 @@ impl Display for Field {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         indent(f)?;
 
-        if self.repeated {
-            write!(f, "repeated ")?;
+        if self.label != Label::None {
+            write!(f, "{} ", self.label)?;
         }
 
         match &self.options {
@@ -77,3 +191,125 @@ impl Display for Field {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::token_stream::stream;
+
+    use super::*;
+
+    #[test]
+    fn label_ok() {
+        let mut ts = stream(&[
+            Type::Optional,
+            Type::Ident("string".to_string()),
+            Type::Ident("foo".to_string()),
+            Type::Assign,
+            Type::IntLit(1),
+            Type::Semicolon,
+        ]);
+
+        let res = Field::try_from(&mut ts);
+        assert!(res.is_ok(), "field parse error {:?}", res);
+
+        let field = res.unwrap();
+        assert_eq!(field.label, Label::Optional);
+        assert_eq!(format!("{field}"), "optional string foo = 1;\n");
+    }
+
+    #[test]
+    fn number_range_err() {
+        for index in [0, FIELD_NUMBER_MAX as u64 + 1] {
+            let mut ts = stream(&[
+                Type::Ident("string".to_string()),
+                Type::Ident("foo".to_string()),
+                Type::Assign,
+                Type::IntLit(index),
+                Type::Semicolon,
+            ]);
+
+            assert!(Field::try_from(&mut ts).is_err(), "accepted field number {index}");
+        }
+    }
+
+    #[test]
+    fn keyword_ident_ok() {
+        let mut ts = stream(&[
+            Type::Repeated,
+            Type::Stream,
+            Type::Option,
+            Type::Assign,
+            Type::IntLit(1),
+            Type::Semicolon,
+        ]);
+
+        let res = Field::try_from(&mut ts);
+        assert!(res.is_ok(), "field parse error {:?}", res);
+
+        let field = res.unwrap();
+        assert_eq!(field.typ, TypeRef::try_from("stream").unwrap());
+        assert_eq!(field.name, "option");
+        assert_eq!(format!("{field}"), "repeated stream option = 1;\n");
+    }
+
+    #[test]
+    fn absolute_type_ok() {
+        let mut ts = stream(&[
+            Type::FullIdent(".google.protobuf.Timestamp".to_string()),
+            Type::Ident("ts".to_string()),
+            Type::Assign,
+            Type::IntLit(1),
+            Type::Semicolon,
+        ]);
+
+        let field = Field::try_from(&mut ts).unwrap();
+        assert!(field.typ.is_absolute());
+        assert_eq!(field.typ.name(), "google.protobuf.Timestamp");
+        assert_eq!(format!("{field}"), ".google.protobuf.Timestamp ts = 1;\n");
+    }
+
+    #[test]
+    fn span_ok() {
+        let tokens = Lexer::new(b"message A {\n  optional string foo = 1;\n}")
+            .tokens()
+            .unwrap();
+        let mut ts = TokenStream::from(tokens[3..9].to_vec());
+
+        let field = Field::try_from(&mut ts).unwrap();
+        assert_eq!(format!("{}", field.span), "2:3 -> 2:27");
+        assert_eq!(field.span.start().offset(), 14);
+        assert_eq!(field.span.len(), 24);
+        assert_eq!(format!("{}", field.typ.span), "2:12 -> 2:18");
+    }
+
+    #[test]
+    fn option_span_ok() {
+        let tokens = Lexer::new(b"int32 foo = 1 [default = -1, json_name = \"f\"];")
+            .tokens()
+            .unwrap();
+        let mut ts = TokenStream::from(tokens);
+
+        let field = Field::try_from(&mut ts).unwrap();
+        let options = field.options.unwrap();
+        assert_eq!(format!("{}", options.span), "1:15 -> 1:46");
+        assert_eq!(format!("{}", options[0].1.span), "1:26 -> 1:28");
+        assert_eq!(format!("{}", options[1].1.span), "1:42 -> 1:45");
+    }
+
+    #[test]
+    fn editions_label_err() {
+        for (source, ok) in [
+            ("optional int32 a = 1;", false),
+            ("required int32 a = 1;", false),
+            ("repeated int32 a = 1;", true),
+            ("int32 a = 1;", true),
+        ] {
+            let tokens = Lexer::new(source.as_bytes()).tokens().unwrap();
+            let mut ts = TokenStream::from(tokens);
+            ts.set_editions(true);
+
+            assert_eq!(Field::try_from(&mut ts).is_ok(), ok, "{source}");
+        }
+    }
+}
@@ -5,12 +5,17 @@ use std::ops::Deref;
 use std::fmt::Display;
 
 use crate::error::ParserError;
+use crate::position::Span;
 use crate::token::Type;
 use crate::token_stream::TokenStream;
+use crate::types::option_name::OptionName;
+use crate::types::option_value::OptionValue;
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct FieldOption {
-    inner: Vec<(String, String)>,
+    inner: Vec<(OptionName, OptionValue)>,
+    // From the opening to the closing bracket
+    pub span: Span,
 }
 
 impl FieldOption {
@@ -18,34 +23,38 @@ impl FieldOption {
         Default::default()
     }
 
-    pub fn push(&mut self, opt: (String, String)) {
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn push(&mut self, opt: (OptionName, OptionValue)) {
         self.inner.push(opt);
     }
 }
 
 impl Deref for FieldOption {
-    type Target = Vec<(String, String)>;
+    type Target = Vec<(OptionName, OptionValue)>;
 
     fn deref(&self) -> &Self::Target {
         &self.inner
     }
 }
 
-impl TryFrom<TokenStream> for FieldOption {
+impl TryFrom<&mut TokenStream> for FieldOption {
     type Error = ParserError;
 
-    // Parsing forwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("field option({:?})", tokens);
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("field option({:?})", tokens.peek());
 
+        let start = tokens.mark();
         let mut opt = FieldOption::new();
 
         tokens.next_eq(Type::LBrack, "field option opening bracket('[')")?;
 
-        while !tokens.is_empty() {
-            let name = tokens.fullident_as_string("field option name")?;
+        loop {
+            let name = OptionName::name_from(tokens, "field option name")?;
             tokens.next_eq(Type::Assign, "field option assignment('=')")?;
-            let value = tokens.constant_as_string("field option value")?;
+            let value = OptionValue::value_from(tokens)?;
 
             opt.push((name, value));
 
@@ -56,7 +65,8 @@ impl TryFrom<TokenStream> for FieldOption {
             tokens.next_eq(Type::Comma, "field option delimiter(',')")?;
         }
 
-        tokens.next_eq(Type::RBrace, "field option closing bracket(']')")?;
+        tokens.next_eq(Type::RBrack, "field option closing bracket(']')")?;
+        opt.set_span(tokens.span_from(start));
 
         Ok(opt)
     }
@@ -74,3 +84,35 @@ impl Display for FieldOption {
         write!(f, "[{values}]")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::token_stream::stream;
+
+    use super::*;
+
+    #[test]
+    fn aggregate_ok() {
+        let mut ts = stream(&[
+            Type::LBrack,
+            Type::Ident("declaration".to_string()),
+            Type::Assign,
+            Type::LBrace,
+            Type::Ident("number".to_string()),
+            Type::Colon,
+            Type::IntLit(4),
+            Type::RBrace,
+            Type::Comma,
+            Type::Ident("packed".to_string()),
+            Type::Assign,
+            Type::BoolLit(true),
+            Type::RBrack,
+        ]);
+
+        let res = FieldOption::try_from(&mut ts);
+        assert!(res.is_ok(), "field option parse error {:?}", res);
+
+        let opt = res.unwrap();
+        assert_eq!(format!("{opt}"), "[declaration={ number: 4 },packed=true]");
+    }
+}
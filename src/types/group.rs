@@ -0,0 +1,193 @@
+use log::debug;
+use std::fmt::Display;
+
+use crate::error::ParserError;
+use crate::indent::{indent, level};
+use crate::position::Span;
+use crate::token::Type;
+use crate::token_stream::TokenStream;
+use crate::types::field::{Field, Label};
+use crate::types::field_option::FieldOption;
+use crate::types::message::Message;
+use crate::types::type_ref::TypeRef;
+
+// Group e.g. `repeated group Result = 1 { required string url = 2; }`
+//
+// A group declares both a field and a nested message type. The field is named after the lowercased
+// group name and its type is the nested message.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Group {
+    pub field: Field,
+    pub message: Message,
+    pub span: Span,
+}
+
+impl Group {
+    pub fn new(field: Field, message: Message) -> Self {
+        Group {
+            field,
+            message,
+            span: Span::default(),
+        }
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn name(&self) -> &str {
+        &self.message.name
+    }
+}
+
+impl TryFrom<&mut TokenStream> for Group {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("group({:?})", tokens.peek());
+        let start = tokens.mark();
+
+        let mut label = Label::None;
+        if let Some(token) = tokens.peek() {
+            if Label::is_label(token.typ()) {
+                label = Label::try_from(token.clone())?;
+                // Pop one since we used peek to determine the label
+                tokens.pop();
+            }
+        }
+
+        tokens.next_eq(Type::Group, "group identifier")?;
+        let token = tokens.next("group name")?;
+        let name = token.as_ident()?;
+        if !name.starts_with(|c: char| c.is_ascii_uppercase()) {
+            return Err(ParserError::unexpected(
+                "group name starting with a capital letter",
+                &token,
+            ));
+        }
+
+        tokens.next_eq(Type::Assign, "group assignment('=')")?;
+        let index = Field::number_from(tokens, "group index")?;
+
+        let options = match tokens.peek_eq(Type::LBrack) {
+            true => Some(FieldOption::try_from(&mut *tokens)?),
+            false => None,
+        };
+
+        tokens.next_eq(Type::LBrace, "group opening brace('{')")?;
+
+        let mut message = Message::new(name.clone());
+        message.push_members(tokens);
+
+        tokens.next_eq(Type::RBrace, "group closing brace('}')")?;
+
+        // The statement declares both, so the field and message share its span
+        let span = tokens.span_from(start);
+        message.set_span(span);
+
+        let typ = TypeRef::try_from(name.as_str())?;
+        let mut field = Field::new(name.to_lowercase(), typ, index, label);
+        field.set_options(options);
+        field.set_span(span);
+
+        let mut res = Self::new(field, message);
+        res.set_span(span);
+
+        Ok(res)
+    }
+}
+
+impl Display for Group {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let i = level(f);
+        indent(f)?;
+
+        if self.field.label != Label::None {
+            write!(f, "{} ", self.field.label)?;
+        }
+
+        write!(f, "group {} = {}", self.message.name, self.field.index)?;
+
+        if let Some(v) = &self.field.options {
+            write!(f, " {v}")?;
+        }
+
+        writeln!(f, " {{")?;
+        for member in &self.message.members {
+            writeln!(f, "{:indent$}", member, indent = i + 1)?;
+        }
+
+        indent(f)?;
+        writeln!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::token_stream::stream;
+
+    use super::*;
+
+    #[test]
+    fn from_ok() {
+        let mut ts = stream(&[
+            Type::Repeated,
+            Type::Group,
+            Type::Ident("Result".to_string()),
+            Type::Assign,
+            Type::IntLit(1),
+            Type::LBrack,
+            Type::Ident("deprecated".to_string()),
+            Type::Assign,
+            Type::BoolLit(true),
+            Type::RBrack,
+            Type::LBrace,
+            Type::RBrace,
+        ]);
+
+        let res = Group::try_from(&mut ts);
+        assert!(res.is_ok(), "group parse error {:?}", res);
+
+        let group = res.unwrap();
+        assert_eq!(group.name(), "Result");
+        assert_eq!(group.field.name, "result");
+        assert_eq!(format!("{}", group.field.typ), "Result");
+        assert!(group.field.is_repeated());
+        assert_eq!(
+            format!("{group}"),
+            "repeated group Result = 1 [deprecated=true] {\n}\n"
+        );
+    }
+
+    #[test]
+    fn span_ok() {
+        let tokens = Lexer::new(b"message A {\n  optional group Foo = 1 {\n  }\n}")
+            .tokens()
+            .unwrap();
+        let mut ts = TokenStream::from(tokens[3..].to_vec());
+
+        let group = Group::try_from(&mut ts).unwrap();
+        assert_eq!(format!("{}", group.span), "2:3 -> 3:4");
+        assert_eq!(group.field.span, group.span);
+        assert_eq!(group.message.span, group.span);
+    }
+
+    #[test]
+    fn lowercase_name_err() {
+        let mut ts = stream(&[
+            Type::Group,
+            Type::Ident("result".to_string()),
+            Type::Assign,
+            Type::IntLit(1),
+            Type::LBrace,
+            Type::RBrace,
+        ]);
+
+        match Group::try_from(&mut ts) {
+            Err(ParserError::UnexpectedToken { found, .. }) => assert_eq!(found, "result"),
+            res => panic!("expected a group name error, got {res:?}"),
+        }
+    }
+}
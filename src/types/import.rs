@@ -1,7 +1,10 @@
 use std::fmt::Display;
 use log::debug;
 
-use crate::{error::ParserError, token::{Token, Type}, token_stream::TokenStream};
+use crate::error::ParserError;
+use crate::position::Span;
+use crate::token::{StringLit, Token, Type};
+use crate::token_stream::TokenStream;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ImportScope {
@@ -16,10 +19,7 @@ impl TryFrom<Token> for ImportScope {
         match token.typ() {
             Type::Weak => Ok(ImportScope::Weak),
             Type::Public => Ok(ImportScope::Public),
-            invalid => Err(ParserError::Syntax(
-                "import scope(weak, public)".to_string(),
-                format!("{:?}", invalid),
-            )),
+            _ => Err(ParserError::unexpected("import scope(weak, public)", &token)),
         }
     }
 }
@@ -37,52 +37,99 @@ impl Display for ImportScope {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Import {
     pub value: String,
+    // The literal as written e.g. `'foo\x2fbar.proto'`, used when printing
+    pub raw: String,
     pub scope: Option<ImportScope>,
+    pub span: Span,
 }
 
 impl Import {
     pub fn new(value: String) -> Self {
-        Import { value, scope: None }
+        Import {
+            raw: StringLit::new(value.as_bytes().to_vec()).raw().to_string(),
+            value,
+            scope: None,
+            span: Span::default(),
+        }
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn set_raw(&mut self, raw: String) {
+        self.raw = raw;
     }
 
     pub fn set_scope(&mut self, scope: Option<ImportScope>) {
         self.scope = scope;
     }
+
+    // Import paths are stored decoded, without the quotes, next to the literal as written
+    fn path_from(tokens: &mut TokenStream) -> Result<(String, String), ParserError> {
+        let lit = tokens.strlit("import value")?;
+
+        match lit.as_str() {
+            Some(v) => Ok((v.to_string(), lit.raw().to_string())),
+            None => Err(ParserError::invalid("import value to be valid UTF-8", lit.raw())),
+        }
+    }
 }
 
-impl TryFrom<TokenStream> for Import {
+impl TryFrom<&mut TokenStream> for Import {
     type Error = ParserError;
 
-    // Parsed backwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("import({:?})", &tokens);
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("import({:?})", tokens.peek());
+        let start = tokens.mark();
 
-        tokens.next_eq(Type::Semicolon, "import line ending(';')")?;
-        let value = tokens.constant_as_string("import value")?;
+        tokens.next_eq(Type::Import, "import identifier")?;
 
         // Check for scope
         let mut scope = None;
-        if !tokens.peek_eq(Type::Import) {
-            scope = match tokens.next_contains(&[Type::Public, Type::Weak], "import scope") {
-                Ok(v) => Some(ImportScope::try_from(v)?),
-                Err(_) => None,
-            };
+        if tokens.peek_eq(Type::Public) || tokens.peek_eq(Type::Weak) {
+            let token = tokens.next_contains(&[Type::Public, Type::Weak], "import scope")?;
+            scope = Some(ImportScope::try_from(token)?);
         }
 
-        tokens.next_eq(Type::Import, "import identifier")?;
+        let (value, raw) = Self::path_from(tokens)?;
+        tokens.next_eq(Type::Semicolon, "import line ending(';')")?;
 
         let mut res = Self::new(value);
+        res.set_raw(raw);
         res.set_scope(scope);
 
-        return Ok(res);
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
     }
 }
 
 impl Display for Import {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.scope {
-            Some(v) => write!(f, "import {v} \"{}\";", self.value),
-            None => write!(f, "import \"{}\";", self.value),
+            Some(v) => write!(f, "import {v} {};", self.raw),
+            None => write!(f, "import {};", self.raw),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+
+    use super::*;
+
+    #[test]
+    fn raw_ok() {
+        let tokens = Lexer::new(br"import public 'foo\x2fbar.proto';").tokens().unwrap();
+
+        let import = Import::try_from(&mut TokenStream::from(tokens)).unwrap();
+        assert_eq!(import.value, "foo/bar.proto");
+        assert_eq!(format!("{import}"), r"import public 'foo\x2fbar.proto';");
+        assert_eq!(
+            format!("{}", Import::new("foo.proto".to_string())),
+            "import \"foo.proto\";"
+        );
+    }
+}
@@ -1,64 +1,250 @@
 use log::debug;
+use std::fmt::Display;
 
 use crate::error::ParserError;
-use crate::token::Type;
+use crate::indent::indent;
+use crate::position::Span;
+use crate::token::{Constant, Type};
 use crate::token_stream::TokenStream;
+use crate::types::field::{Field, Label};
 use crate::types::field_option::FieldOption;
+use crate::types::message::{Message, MessageMember};
+use crate::types::option_field::OptionField;
+use crate::types::option_name::{OptionName, OptionNamePart};
+use crate::types::option_value::OptionValue;
+use crate::types::type_ref::{Scalar, TypeRef, TypeRefKind};
+
+// Map e.g. `map<string, Project> projects = 3;`
+//
+// On the wire a map is a repeated field of a synthetic nested message named after the field e.g.
+// `ProjectsEntry`, holding a `key` and a `value` field.
+// https://protobuf.dev/programming-guides/proto3/#maps
+
+const MAP_URL: &str = "https://protobuf.dev/programming-guides/proto3/#maps";
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Map {
-    name: String,
-    key: String,
-    value: String,
-    index: i32,
-    options: Option<FieldOption>,
+    pub name: String,
+    pub key: Scalar,
+    pub value: TypeRef,
+    pub index: u32,
+    pub options: Option<FieldOption>,
+    pub span: Span,
 }
 
 impl Map {
-    pub fn new(name: String, key: String, value: String, index: i32) -> Self {
+    pub fn new(name: String, key: Scalar, value: TypeRef, index: u32) -> Self {
         Map {
             name,
             key,
             value,
             index,
             options: None,
+            span: Span::default(),
         }
     }
 
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
     pub fn set_options(&mut self, options: Option<FieldOption>) {
         self.options = options;
     }
+
+    // Name of the synthetic entry message, the field name in camel case suffixed with `Entry`
+    // e.g. `project_ids` becomes `ProjectIdsEntry`.
+    pub fn entry_name(&self) -> String {
+        let mut name = String::new();
+        let mut upper = true;
+
+        for ch in self.name.chars() {
+            match ch {
+                '_' => upper = true,
+                ch if upper => {
+                    name.push(ch.to_ascii_uppercase());
+                    upper = false;
+                }
+                ch => name.push(ch),
+            }
+        }
+
+        format!("{name}Entry")
+    }
+
+    // The synthetic entry message e.g.
+    // `message ProjectsEntry { option map_entry = true; string key = 1; Project value = 2; }`
+    pub fn entry(&self) -> Message {
+        let mut message = Message::new(self.entry_name());
+
+        let map_entry = OptionField::new(
+            OptionName::new(vec![OptionNamePart::Simple("map_entry".to_string())]),
+            OptionValue::from(Constant::Bool(true)),
+        );
+
+        message.push(MessageMember::from(map_entry));
+        message.push(MessageMember::from(Field::new(
+            "key".to_string(),
+            TypeRef::from(self.key),
+            1,
+            Label::None,
+        )));
+        message.push(MessageMember::from(Field::new(
+            "value".to_string(),
+            self.value.clone(),
+            2,
+            Label::None,
+        )));
+        message.set_span(self.span);
+
+        message
+    }
+
+    // The repeated field of entry messages the map is encoded as.
+    pub fn field(&self) -> Field {
+        let mut field = Field::new(
+            self.name.clone(),
+            TypeRef::new(TypeRefKind::Named {
+                absolute: false,
+                parts: vec![self.entry_name()],
+            }),
+            self.index,
+            Label::Repeated,
+        );
+        field.set_options(self.options.clone());
+        field.set_span(self.span);
+
+        field
+    }
 }
 
-impl TryFrom<TokenStream> for Map {
+impl TryFrom<&mut TokenStream> for Map {
     type Error = ParserError;
 
-    // Parsing backwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("map({:?})", tokens);
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("map({:?})", tokens.peek());
+        let start = tokens.mark();
 
-        tokens.next_eq(Type::Semicolon, "map line ending(';')")?;
+        tokens.next_eq(Type::Map, "map identifier")?;
+        tokens.next_eq(Type::LT, "map opening('<')")?;
+        let key = tokens.ident_as_string("map key type")?;
+        let key = match Scalar::try_from(key.as_str()) {
+            Ok(v) if v.is_integral_or_string() => v,
+            _ => return Err(ParserError::expected(
+                format!(
+                    "map key type to be an integral or string type, check {MAP_URL} for more info"
+                ),
+                key,
+            )),
+        };
+        tokens.next_eq(Type::Comma, "map key-value delimiter(',')")?;
+        let value = TypeRef::type_from(tokens, "map value type")?;
+        tokens.next_eq(Type::GT, "map closing('>')")?;
+        let name = tokens.ident_as_string("map name")?;
+        tokens.next_eq(Type::Assign, "map assigment('=')")?;
+        let index = Field::number_from(tokens, "map index")?;
 
         // Check for field options
         let mut options = None;
-        if tokens.peek_eq(Type::RBrack) {
-            let option_tokens = tokens.select_block(Type::RBrack, Type::LBrack);
-            options = Some(FieldOption::try_from(option_tokens)?);
+        if tokens.peek_eq(Type::LBrack) {
+            options = Some(FieldOption::try_from(&mut *tokens)?);
         }
 
-        let index = tokens.intlit_as_i32("map index")?;
-        tokens.next_eq(Type::Assign, "map assigment('=')")?;
-        let name = tokens.ident_as_string("map name")?;
-        tokens.next_eq(Type::GT, "map closing('>')")?;
-        let value = tokens.fullident_as_string("map value type")?;
-        tokens.next_eq(Type::Comma, "map key-value delimiter(',')")?;
-        let key = tokens.ident_as_string("map key type")?;
-        tokens.next_eq(Type::LT, "map opening('<')")?;
-        tokens.next_eq(Type::Map, "map identifier")?;
+        tokens.next_eq(Type::Semicolon, "map line ending(';')")?;
 
         let mut map = Self::new(name, key, value, index);
         map.set_options(options);
 
-        return Ok(map);
+        map.set_span(tokens.span_from(start));
+
+        Ok(map)
+    }
+}
+
+impl Display for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        indent(f)?;
+
+        write!(
+            f,
+            "map<{}, {}> {} = {}",
+            self.key, self.value, self.name, self.index
+        )?;
+
+        match &self.options {
+            Some(v) => writeln!(f, " {v};"),
+            None => writeln!(f, ";"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::token_stream::stream;
+
+    use super::*;
+
+    #[test]
+    fn from_ok() {
+        let mut ts = stream(&[
+            Type::Map,
+            Type::LT,
+            Type::Ident("string".to_string()),
+            Type::Comma,
+            Type::FullIdent("foo.Project".to_string()),
+            Type::GT,
+            Type::Ident("project_ids".to_string()),
+            Type::Assign,
+            Type::IntLit(3),
+            Type::Semicolon,
+        ]);
+
+        let res = Map::try_from(&mut ts);
+        assert!(res.is_ok(), "map parse error {:?}", res);
+
+        let map = res.unwrap();
+        assert_eq!(map.key, Scalar::String);
+        assert_eq!(
+            format!("{map}"),
+            "map<string, foo.Project> project_ids = 3;\n"
+        );
+
+        assert_eq!(map.entry_name(), "ProjectIdsEntry");
+        let entry = map.entry();
+        assert_eq!(entry.name, "ProjectIdsEntry");
+        assert_eq!(entry.members.len(), 3);
+        assert_eq!(
+            entry.members[2],
+            MessageMember::from(Field::new(
+                "value".to_string(),
+                TypeRef::try_from("foo.Project").unwrap(),
+                2,
+                Label::None
+            ))
+        );
+
+        let field = map.field();
+        assert!(field.is_repeated());
+        assert_eq!(field.typ.name(), "ProjectIdsEntry");
+    }
+
+    #[test]
+    fn key_type_err() {
+        for key in ["float", "double", "bytes", "Foo"] {
+            let mut ts = stream(&[
+                Type::Map,
+                Type::LT,
+                Type::Ident(key.to_string()),
+                Type::Comma,
+                Type::Ident("string".to_string()),
+                Type::GT,
+                Type::Ident("foo".to_string()),
+                Type::Assign,
+                Type::IntLit(1),
+                Type::Semicolon,
+            ]);
+
+            assert!(Map::try_from(&mut ts).is_err(), "accepted map key type {key}");
+        }
     }
 }
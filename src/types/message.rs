@@ -3,40 +3,77 @@ use std::fmt::Display;
 
 use crate::error::ParserError;
 use crate::indent::{indent, level};
+use crate::position::Span;
+use crate::scope::Scope;
 use crate::token::Type;
 use crate::token_stream::TokenStream;
 use crate::types::enumerate::Enum;
-use crate::types::field::Field;
+use crate::types::empty::Empty;
+use crate::types::extend::Extend;
+use crate::types::extension_range::ExtensionRange;
+use crate::types::field::{Field, Label};
+use crate::types::group::Group;
 use crate::types::map::Map;
-use crate::types::oneof::Oneof;
+use crate::types::oneof::{Oneof, OneofMember};
 use crate::types::option_field::OptionField;
+use crate::types::reserved::Reserved;
+use crate::types::syntax::Syntax;
 
 use super::comment::{BlockComment, LineComment};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum MessageMember {
+    Empty(Empty),
     Enum(Enum),
+    Extend(Extend),
+    Extensions(ExtensionRange),
     Field(Field),
+    Group(Group),
     Map(Map),
     Message(Message),
     Oneof(Oneof),
     Option(OptionField),
+    Reserved(Reserved),
     LineComment(LineComment),
     BlockComment(BlockComment),
 }
 
+impl From<Empty> for MessageMember {
+    fn from(value: Empty) -> Self {
+        Self::Empty(value)
+    }
+}
+
 impl From<Enum> for MessageMember {
     fn from(value: Enum) -> Self {
         MessageMember::Enum(value)
     }
 }
 
+impl From<Extend> for MessageMember {
+    fn from(value: Extend) -> Self {
+        MessageMember::Extend(value)
+    }
+}
+
+impl From<ExtensionRange> for MessageMember {
+    fn from(value: ExtensionRange) -> Self {
+        MessageMember::Extensions(value)
+    }
+}
+
 impl From<Field> for MessageMember {
     fn from(value: Field) -> Self {
         MessageMember::Field(value)
     }
 }
 
+impl From<Group> for MessageMember {
+    fn from(value: Group) -> Self {
+        MessageMember::Group(value)
+    }
+}
+
 impl From<Map> for MessageMember {
     fn from(value: Map) -> Self {
         MessageMember::Map(value)
@@ -61,6 +98,12 @@ impl From<OptionField> for MessageMember {
     }
 }
 
+impl From<Reserved> for MessageMember {
+    fn from(value: Reserved) -> Self {
+        MessageMember::Reserved(value)
+    }
+}
+
 impl From<BlockComment> for MessageMember {
     fn from(value: BlockComment) -> Self {
         MessageMember::BlockComment(value)
@@ -73,9 +116,86 @@ impl From<LineComment> for MessageMember {
     }
 }
 
+impl MessageMember {
+    // Names the member defines in the message scope, fields of a oneof belong to the message.
+    fn definitions(&self) -> Vec<(&str, Span)> {
+        match self {
+            Self::Enum(v) => vec![(&v.name, v.span)],
+            Self::Field(v) => vec![(&v.name, v.span)],
+            Self::Group(v) => vec![(&v.field.name, v.span)],
+            Self::Map(v) => vec![(&v.name, v.span)],
+            Self::Message(v) => vec![(&v.name, v.span)],
+            Self::Oneof(v) => {
+                let mut res = vec![(v.name.as_str(), v.span)];
+                for member in &v.members {
+                    match member {
+                        OneofMember::Field(f) => res.push((&f.name, f.span)),
+                        OneofMember::Group(g) => res.push((&g.field.name, g.span)),
+                        _ => (),
+                    }
+                }
+                res
+            }
+            _ => vec![],
+        }
+    }
+}
+
+impl TryFrom<&mut TokenStream> for MessageMember {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        let typ = match tokens.peek() {
+            Some(token) => token.typ(),
+            None => return Err(tokens.eof("message member")),
+        };
+
+        let member = match typ {
+            Type::Enum => Self::from(Enum::try_from(tokens)?),
+            Type::Extend => Self::from(Extend::try_from(tokens)?),
+            Type::Extensions => Self::from(ExtensionRange::try_from(tokens)?),
+            // `map` is only a keyword when followed by its key-value types e.g. `map<..>`
+            Type::Map if tokens.peeks_eq(&[Type::Map, Type::LT]) => {
+                Self::from(Map::try_from(tokens)?)
+            }
+            Type::Message => Self::from(Message::try_from(tokens)?),
+            Type::Oneof => Self::from(Oneof::try_from(tokens)?),
+            Type::Option => Self::from(OptionField::try_from(tokens)?),
+            Type::Reserved => Self::from(Reserved::try_from(tokens)?),
+            Type::Semicolon => Self::from(Empty::try_from(tokens)?),
+            Type::LineComment(_) => Self::from(LineComment::try_from(tokens)?),
+            Type::BlockComment(_) => Self::from(BlockComment::try_from(tokens)?),
+            Type::Group | Type::Optional | Type::Required | Type::Repeated
+                if tokens.is_group() =>
+            {
+                Self::from(Group::try_from(tokens)?)
+            }
+            _field => Self::from(Field::try_from(tokens)?),
+        };
+
+        Ok(member)
+    }
+}
+
 impl Display for MessageMember {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self)
+        let i = level(f);
+
+        match self {
+            Self::Empty(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Enum(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Extend(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Extensions(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Field(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Group(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Map(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Message(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Oneof(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Option(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Reserved(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::LineComment(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::BlockComment(v) => write!(f, "{:indent$}", v, indent = i),
+        }
     }
 }
 
@@ -83,6 +203,7 @@ impl Display for MessageMember {
 pub struct Message {
     pub name: String,
     pub members: Vec<MessageMember>,
+    pub span: Span,
 }
 
 impl Message {
@@ -93,84 +214,115 @@ impl Message {
         }
     }
 
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
     pub fn push(&mut self, value: MessageMember) {
         self.members.push(value)
     }
-}
 
-impl TryFrom<TokenStream> for Message {
-    type Error = ParserError;
+    // Field and oneof names of the message, synthetic oneofs must not collide with them.
+    fn member_names(&self) -> Vec<String> {
+        let mut names = vec![];
+
+        for member in &self.members {
+            match member {
+                MessageMember::Field(v) => names.push(v.name.clone()),
+                MessageMember::Group(v) => names.push(v.field.name.clone()),
+                MessageMember::Map(v) => names.push(v.name.clone()),
+                MessageMember::Oneof(v) => {
+                    names.push(v.name.clone());
+                    v.members.iter().for_each(|m| match m {
+                        OneofMember::Field(field) => names.push(field.name.clone()),
+                        OneofMember::Group(group) => names.push(group.field.name.clone()),
+                        _ => (),
+                    });
+                }
+                _ => (),
+            }
+        }
 
-    // Parsing forwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("message({:?})", tokens);
+        names
+    }
 
-        tokens.next_eq(Type::Message, "message identifier")?;
-        let name = tokens.ident_as_string("message name")?;
+    // Proto3 `optional` fields are wrapped in synthetic oneofs the same way protoc does it. Each
+    // oneof is named after its field prefixed with `_` unless it already starts with one, and
+    // prefixed with `X` until the name no longer collides with another field or oneof. Proto2
+    // `optional` has explicit presence without any oneof so there are none outside proto3.
+    pub fn synthetic_oneofs(&self, syntax: &Syntax) -> Vec<Oneof> {
+        if !syntax.is_proto3() {
+            return vec![];
+        }
 
-        let mut res = Message::new(name);
+        let mut names = self.member_names();
+        let mut res = vec![];
 
-        while !tokens.is_empty() {
-            let peek_token = match tokens.peek() {
-                Some(v) => v,
-                None => {
-                    return Err(ParserError::Syntax(
-                        "message member".to_string(),
-                        "nothing".to_string(),
-                    ))
-                }
+        for member in &self.members {
+            let field = match member {
+                MessageMember::Field(v) if v.label == Label::Optional => v,
+                _ => continue,
             };
 
-            let member = match peek_token.typ() {
-                Type::Enum => {
-                    let block = tokens.select_block(Type::LBrace, Type::RBrace);
-                    MessageMember::from(Enum::try_from(block)?)
-                }
-                Type::Map => {
-                    let line = tokens.select_until(Type::Semicolon);
-                    MessageMember::from(Enum::try_from(line)?)
-                }
-                Type::Message => {
-                    let block = tokens.select_block(Type::LBrace, Type::RBrace);
-                    MessageMember::from(Message::try_from(block)?)
-                }
-                Type::Oneof => {
-                    let block = tokens.select_block(Type::LBrace, Type::RBrace);
-                    MessageMember::from(Oneof::try_from(block)?)
-                }
-                Type::Option => {
-                    let line = tokens.select_until(Type::Semicolon);
-                    MessageMember::from(OptionField::try_from(line)?)
-                }
-                Type::RBrace => {
-                    break;
-                }
-                Type::Slash => {
-                    if tokens.is_line_comment() {
-                        let line = tokens.select_line_comment()?;
-                        MessageMember::from(LineComment::from(line))
-                    } else if tokens.is_block_comment() {
-                        let block = tokens.select_block_comment()?;
-                        MessageMember::from(BlockComment::from(block))
-                    } else {
-                        return Err(ParserError::Syntax(
-                            "message comment".to_string(),
-                            format!("message tokens: {tokens}"),
-                        ));
-                    }
-                }
-                _field => {
-                    let line = tokens.select_until(Type::Semicolon);
-                    MessageMember::from(Field::try_from(line)?)
-                }
+            let mut name = match field.name.starts_with('_') {
+                true => field.name.clone(),
+                false => format!("_{}", field.name),
             };
+            while names.contains(&name) {
+                name.insert(0, 'X');
+            }
+            names.push(name.clone());
 
-            tokens.next_eq(Type::RBrace, "message closing brace('}')")?;
+            let mut oneof = Oneof::new(name);
+            oneof.push(OneofMember::from(field.clone()));
+            res.push(oneof);
+        }
+
+        res
+    }
 
-            res.push(member);
+    // Parses the message body up until the closing brace which is left for the caller. Members
+    // failing to parse or defining a name twice are reported, the former are skipped.
+    pub(crate) fn push_members(&mut self, tokens: &mut TokenStream) {
+        let mut scope = Scope::new();
+
+        while tokens.peek().is_some_and(|t| t.typ() != &Type::RBrace) {
+            let start = tokens.mark();
+
+            match MessageMember::try_from(&mut *tokens) {
+                Ok(member) => {
+                    for (name, span) in member.definitions() {
+                        if let Err(e) = scope.define(name, span) {
+                            tokens.report(e);
+                        }
+                    }
+                    self.push(member)
+                }
+                Err(e) => tokens.recover(start, e),
+            }
         }
+    }
+}
+
+impl TryFrom<&mut TokenStream> for Message {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("message({:?})", tokens.peek());
+        let start = tokens.mark();
+
+        tokens.next_eq(Type::Message, "message identifier")?;
+        let name = tokens.ident_as_string("message name")?;
+        tokens.next_eq(Type::LBrace, "message opening brace('{')")?;
+
+        let mut res = Message::new(name);
+        res.push_members(tokens);
+
+        tokens.next_eq(Type::RBrace, "message closing brace('}')")?;
 
-        return Ok(res);
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
     }
 }
 
@@ -189,3 +341,101 @@ impl Display for Message {
         writeln!(f, "}}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::cst::Cst;
+    use crate::types::type_ref::{Scalar, TypeRef};
+
+    use super::*;
+
+    #[test]
+    fn nested_ok() {
+        let source = "message A {\n  message B { enum C { X = 0; } }\n  int32 b = 1; // b\n}\n;";
+        let mut tokens = Cst::try_from(source).unwrap().token_stream();
+
+        let res = Message::try_from(&mut tokens);
+        assert!(res.is_ok(), "message parse error {:?}", res);
+
+        // Only the message is consumed, the trailing empty statement is left
+        assert!(tokens.peek_eq(Type::Semicolon));
+
+        let message = res.unwrap();
+        assert_eq!(message.members.len(), 3);
+        assert_eq!(format!("{}", message.span), "1:1 -> 4:2");
+        match &message.members[0] {
+            MessageMember::Message(v) => {
+                assert_eq!(v.name, "B");
+                assert_eq!(format!("{}", v.span), "2:3 -> 2:34");
+                assert!(matches!(&v.members[..], [MessageMember::Enum(_)]));
+            }
+            invalid => panic!("expected message, got {invalid}"),
+        }
+        assert!(matches!(message.members[2], MessageMember::LineComment(_)));
+    }
+
+    #[test]
+    fn synthetic_oneofs_ok() {
+        let mut message = Message::new("Foo".to_string());
+
+        let fields = [
+            Field::new("bar".to_string(), TypeRef::from(Scalar::Int32), 1, Label::Optional),
+            Field::new("_baz".to_string(), TypeRef::from(Scalar::Int32), 2, Label::None),
+            Field::new("baz".to_string(), TypeRef::from(Scalar::String), 3, Label::Optional),
+            Field::new("qux".to_string(), TypeRef::from(Scalar::String), 4, Label::Repeated),
+        ];
+
+        for field in fields {
+            message.push(MessageMember::from(field));
+        }
+
+        let syntax = Syntax::new("proto3".to_string());
+        let oneofs = message.synthetic_oneofs(&syntax);
+        let names = oneofs.iter().map(|o| o.name.as_str()).collect::<Vec<&str>>();
+        assert_eq!(names, vec!["_bar", "X_baz"]);
+
+        match &oneofs[0].members[..] {
+            [OneofMember::Field(field)] => assert_eq!(field.name, "bar"),
+            invalid => panic!("unexpected synthetic oneof members {:?}", invalid),
+        }
+    }
+
+    #[test]
+    fn synthetic_oneofs_names_ok() {
+        let source = "message Foo {
+  message _bar {}
+  enum _baz { X = 0; }
+  map<string, string> qux = 1;
+  optional int32 bar = 2;
+  optional int32 baz = 3;
+  optional int32 _qux = 4;
+  optional int32 _quux = 5;
+  oneof X_quux { int32 quux_value = 6; }
+}";
+        let mut tokens = Cst::try_from(source).unwrap().token_stream();
+        let message = Message::try_from(&mut tokens).unwrap();
+
+        // Nested types and map entries don't collide with the oneof names, field names already
+        // starting with `_` aren't prefixed again but still collide with the field itself.
+        let syntax = Syntax::new("proto3".to_string());
+        let oneofs = message.synthetic_oneofs(&syntax);
+        let names = oneofs.iter().map(|o| o.name.as_str()).collect::<Vec<&str>>();
+        assert_eq!(names, vec!["_bar", "_baz", "X_qux", "XX_quux"]);
+    }
+
+    #[test]
+    fn synthetic_oneofs_proto2_ok() {
+        let mut message = Message::new("Foo".to_string());
+        message.push(MessageMember::from(Field::new(
+            "bar".to_string(),
+            TypeRef::from(Scalar::Int32),
+            1,
+            Label::Optional,
+        )));
+
+        assert!(message.synthetic_oneofs(&Syntax::default()).is_empty());
+        assert!(message
+            .synthetic_oneofs(&Syntax::new("proto2".to_string()))
+            .is_empty());
+    }
+}
@@ -1,16 +1,24 @@
 pub mod comment;
+pub mod edition;
+pub mod empty;
 pub mod enumerate;
 pub mod enum_field;
+pub mod extend;
+pub mod extension_range;
 pub mod field;
 pub mod field_option;
+pub mod group;
 pub mod import;
 pub mod map;
 pub mod message;
 pub mod oneof;
 pub mod option_field;
+pub mod option_name;
+pub mod option_value;
 pub mod package;
 pub mod proto;
+pub mod reserved;
 pub mod rpc;
-pub mod rpc_option;
 pub mod service;
 pub mod syntax;
+pub mod type_ref;
@@ -3,20 +3,31 @@ use log::debug;
 
 use crate::error::ParserError;
 use crate::indent::{indent, level};
+use crate::position::Span;
 use crate::token::Type;
 use crate::token_stream::TokenStream;
+use crate::types::empty::Empty;
 use crate::types::field::Field;
+use crate::types::group::Group;
 use crate::types::option_field::OptionField;
 use crate::types::comment::{BlockComment, LineComment};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum OneofMember {
     BlockComment(BlockComment),
+    Empty(Empty),
     Field(Field),
+    Group(Group),
     LineComment(LineComment),
     Option(OptionField),
 }
 
+impl From<Empty> for OneofMember {
+    fn from(value: Empty) -> Self {
+        Self::Empty(value)
+    }
+}
+
 impl From<BlockComment> for OneofMember {
     fn from(value: BlockComment) -> Self {
         Self::BlockComment(value)
@@ -29,6 +40,12 @@ impl From<Field> for OneofMember {
     }
 }
 
+impl From<Group> for OneofMember {
+    fn from(value: Group) -> Self {
+        Self::Group(value)
+    }
+}
+
 impl From<LineComment> for OneofMember {
     fn from(value: LineComment) -> Self {
         Self::LineComment(value)
@@ -41,9 +58,40 @@ impl From<OptionField> for OneofMember {
     }
 }
 
+impl TryFrom<&mut TokenStream> for OneofMember {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        let typ = match tokens.peek() {
+            Some(token) => token.typ(),
+            None => return Err(tokens.eof("oneof member")),
+        };
+
+        let member = match typ {
+            Type::Option => Self::from(OptionField::try_from(tokens)?),
+            Type::LineComment(_) => Self::from(LineComment::try_from(tokens)?),
+            Type::BlockComment(_) => Self::from(BlockComment::try_from(tokens)?),
+            Type::Semicolon => Self::from(Empty::try_from(tokens)?),
+            Type::Group if tokens.is_group() => Self::from(Group::try_from(tokens)?),
+            _field => Self::from(Field::try_from(tokens)?),
+        };
+
+        Ok(member)
+    }
+}
+
 impl Display for OneofMember {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self)
+        let i = level(f);
+
+        match self {
+            Self::BlockComment(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Empty(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Field(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Group(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::LineComment(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Option(v) => write!(f, "{:indent$}", v, indent = i),
+        }
     }
 }
 
@@ -51,6 +99,7 @@ impl Display for OneofMember {
 pub struct Oneof {
     pub name: String,
     pub members: Vec<OneofMember>,
+    pub span: Span,
 }
 
 impl Oneof {
@@ -58,20 +107,25 @@ impl Oneof {
         Oneof {
             name,
             members: vec![],
+            span: Span::default(),
         }
     }
 
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
     pub fn push(&mut self, member: OneofMember) {
         self.members.push(member);
     }
 }
 
-impl TryFrom<TokenStream> for Oneof {
+impl TryFrom<&mut TokenStream> for Oneof {
     type Error = ParserError;
 
-    // Parsing forwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("oneof({:?})", tokens);
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("oneof({:?})", tokens.peek());
+        let start = tokens.mark();
 
         tokens.next_eq(Type::Oneof, "oneof identifier")?;
         let name = tokens.ident_as_string("oneof name")?;
@@ -79,45 +133,20 @@ impl TryFrom<TokenStream> for Oneof {
 
         let mut res = Self::new(name);
 
-        while !tokens.is_empty() {
-            let peek_token = match tokens.peek() {
-                Some(v) => v,
-                None => return Err(ParserError::Syntax("oneof member".to_string(), "unexpected oneof ending".to_string())),
-            };
-
-            let member = match peek_token.typ() {
-                Type::Option => {
-                    let option_tokens = tokens.select_until(Type::Semicolon);
-                    OneofMember::from(OptionField::try_from(option_tokens)?)
-                }
-                Type::Slash => {
-                    if tokens.is_line_comment() {
-                        let line = tokens.select_line_comment()?;
-                        OneofMember::from(LineComment::from(line))
-                    } else if tokens.is_block_comment() {
-                        let block = tokens.select_block_comment()?;
-                        OneofMember::from(BlockComment::from(block))
-                    } else {
-                        return Err(ParserError::Syntax(
-                            "oneof comment".to_string(),
-                            format!("oneof tokens: {tokens}"),
-                        ));
-                    }
-                }
-                Type::RBrace => break,
-                _field => {
-                    let field_tokens = tokens.select_until(Type::Semicolon);
-                    OneofMember::from(Field::try_from(field_tokens)?)
-
-                }
-            };
-
-            res.push(member);
+        while tokens.peek().is_some_and(|t| t.typ() != &Type::RBrace) {
+            let start = tokens.mark();
+
+            match OneofMember::try_from(&mut *tokens) {
+                Ok(member) => res.push(member),
+                Err(e) => tokens.recover(start, e),
+            }
         }
 
         tokens.next_eq(Type::RBrace, "oneof closing brace('}')")?;
 
-        return Ok(res);
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
     }
 }
 
@@ -3,42 +3,57 @@ use std::fmt::Display;
 
 use crate::error::ParserError;
 use crate::indent::indent;
+use crate::position::Span;
 use crate::token::Type;
 use crate::token_stream::TokenStream;
+use crate::types::option_name::OptionName;
+use crate::types::option_value::OptionValue;
 
-// OptionField e.g. `option foo = true;`
+// OptionField e.g. `option foo = true;` or `option (foo) = { bar: 1 };`
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct OptionField {
-    name: String,
-    value: String,
+    pub name: OptionName,
+    pub value: OptionValue,
+    pub span: Span,
 }
 
 impl OptionField {
-    pub fn new(name: String, value: String) -> Self {
+    pub fn new(name: OptionName, value: OptionValue) -> Self {
         Self {
             name,
             value,
+            span: Span::default(),
         }
     }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
 }
 
-impl TryFrom<TokenStream> for OptionField {
+impl TryFrom<&mut TokenStream> for OptionField {
     type Error = ParserError;
 
-    // Parsing backwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("option field({:?})", &tokens);
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("option field({:?})", tokens.peek());
+        let start = tokens.mark();
 
-        tokens.next_eq(Type::Semicolon, "option line ending(';')")?;
-        let value = tokens.constant_as_string("option value")?;
+        tokens.next_eq(Type::Option, "option identifier")?;
+        let name = OptionName::name_from(tokens, "option name")?;
         tokens.next_eq(Type::Assign, "option assignment('=')")?;
 
-        let name = tokens.optname_as_string("option name")?;
+        // Lists are only allowed within message literals
+        let value = match tokens.peek_eq(Type::LBrace) || tokens.peek_eq(Type::LT) {
+            true => OptionValue::message_from(tokens)?,
+            false => OptionValue::constant_from(tokens)?,
+        };
 
-        tokens.next_eq(Type::Option, "option identifier")?;
+        tokens.next_eq(Type::Semicolon, "option line ending(';')")?;
+
+        let mut res = Self::new(name, value);
 
-        let res = Self::new(name, value);
+        res.set_span(tokens.span_from(start));
 
         Ok(res)
     }
@@ -51,3 +66,42 @@ impl Display for OptionField {
         writeln!(f, "option {} = {};", self.name, self.value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::token::StringLit;
+    use crate::token_stream::stream;
+
+    use super::*;
+
+    #[test]
+    fn message_literal_ok() {
+        let mut ts = stream(&[
+            Type::Option,
+            Type::LParen,
+            Type::FullIdent("google.api.http".to_string()),
+            Type::RParen,
+            Type::Assign,
+            Type::LBrace,
+            Type::Ident("get".to_string()),
+            Type::Colon,
+            Type::StringLit(StringLit::new(b"/v1".to_vec())),
+            Type::Ident("body".to_string()),
+            Type::Colon,
+            Type::StringLit(StringLit::new(b"*".to_vec())),
+            Type::RBrace,
+            Type::Semicolon,
+        ]);
+
+        let res = OptionField::try_from(&mut ts);
+        assert!(res.is_ok(), "option parse error {:?}", res);
+
+        let option = res.unwrap();
+        assert!(option.name.is_custom());
+        assert!(option.value.as_message().is_some());
+        assert_eq!(
+            format!("{option}"),
+            "option (google.api.http) = { get: \"/v1\" body: \"*\" };\n"
+        );
+    }
+}
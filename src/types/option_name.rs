@@ -0,0 +1,287 @@
+// Option names e.g. `deprecated`, `features.field_presence` or `(foo.bar).baz`
+//
+// Every part of the name is either a simple identifier or an extension name enclosed in
+// parentheses. Options starting with an extension name are custom options.
+// https://protobuf.com/docs/language-spec#option-names
+use std::fmt::Display;
+
+use crate::error::ParserError;
+use crate::token::Type;
+use crate::token_stream::TokenStream;
+
+const OPTION_NAME_URL: &str = "https://protobuf.com/docs/language-spec#option-names";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionNamePart {
+    Simple(String),
+    Extension(String),
+}
+
+impl OptionNamePart {
+    fn is_ident(value: &str) -> bool {
+        let mut chars = value.chars();
+
+        match chars.next() {
+            Some(ch) if ch.is_ascii_alphabetic() || ch == '_' => (),
+            _ => return false,
+        }
+
+        chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+    }
+
+    // Extension names are full identifiers which may be fully qualified e.g. `.foo.bar`
+    fn is_extension(value: &str) -> bool {
+        let value = value.strip_prefix('.').unwrap_or(value);
+        value.split('.').all(Self::is_ident)
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Simple(v) => v,
+            Self::Extension(v) => v,
+        }
+    }
+}
+
+impl Display for OptionNamePart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Simple(v) => write!(f, "{v}"),
+            Self::Extension(v) => write!(f, "({v})"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptionName {
+    pub parts: Vec<OptionNamePart>,
+}
+
+impl OptionName {
+    pub fn new(parts: Vec<OptionNamePart>) -> Self {
+        Self { parts }
+    }
+
+    pub fn is_custom(&self) -> bool {
+        matches!(self.parts.first(), Some(OptionNamePart::Extension(_)))
+    }
+
+    pub fn is_builtin(&self) -> bool {
+        !self.is_custom()
+    }
+
+    // Returns the name if it's a single simple identifier e.g. `packed`
+    pub fn as_simple(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [OptionNamePart::Simple(v)] => Some(v),
+            _ => None,
+        }
+    }
+
+    // Pops the parts of a name, whitespace may appear around any of the tokens e.g.
+    // `( foo.bar ) .baz`. A dot glued to the following identifier is lexed as part of it.
+    pub(crate) fn name_from(
+        tokens: &mut TokenStream,
+        expect_msg: &str,
+    ) -> Result<Self, ParserError> {
+        let mut parts = vec![];
+        // Whether a part is expected next, true at the start and after a dot
+        let mut expect_part = true;
+
+        while let Some(token) = tokens.peek() {
+            match token.typ().clone() {
+                Type::LParen if expect_part => {
+                    tokens.pop();
+                    let token = tokens.next("extension name")?;
+                    let name = token.as_full_ident()?;
+                    if !OptionNamePart::is_extension(&name) {
+                        return Err(ParserError::unexpected(
+                            format!("extension name, check {OPTION_NAME_URL} for more info"),
+                            &token,
+                        ));
+                    }
+                    tokens.next_eq(Type::RParen, "extension name closing parenthesis(')')")?;
+
+                    parts.push(OptionNamePart::Extension(name));
+                    expect_part = false;
+                }
+                Type::Dot if !expect_part => {
+                    tokens.pop();
+                    expect_part = true;
+                }
+                Type::FullIdent(v) if !expect_part && v.starts_with('.') => {
+                    tokens.pop();
+                    parts.extend(v[1..].split('.').map(|p| OptionNamePart::Simple(p.into())));
+                }
+                _ if expect_part => {
+                    let token = tokens.next(expect_msg)?;
+                    let name = token.as_full_ident()?;
+                    if name.starts_with('.') {
+                        return Err(ParserError::unexpected(
+                            format!("{expect_msg}, check {OPTION_NAME_URL} for more info"),
+                            &token,
+                        ));
+                    }
+
+                    parts.extend(name.split('.').map(|p| OptionNamePart::Simple(p.into())));
+                    expect_part = false;
+                }
+                _ => break,
+            }
+        }
+
+        if expect_part {
+            return Err(tokens.unexpected(&format!(
+                "{expect_msg}, check {OPTION_NAME_URL} for more info"
+            )));
+        }
+
+        Ok(Self::new(parts))
+    }
+}
+
+impl TryFrom<&str> for OptionName {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let invalid = || {
+            ParserError::expected(
+                format!("option name, check {OPTION_NAME_URL} for more info"),
+                value,
+            )
+        };
+
+        let mut parts = vec![];
+        let mut rest = value;
+
+        loop {
+            let (part, tail) = match rest.strip_prefix('(') {
+                Some(extension) => {
+                    let end = extension.find(')').ok_or_else(invalid)?;
+                    let name = &extension[..end];
+
+                    if !OptionNamePart::is_extension(name) {
+                        return Err(invalid());
+                    }
+
+                    (
+                        OptionNamePart::Extension(name.to_string()),
+                        &extension[end + 1..],
+                    )
+                }
+                None => {
+                    let end = rest.find('.').unwrap_or(rest.len());
+                    let name = &rest[..end];
+
+                    if !OptionNamePart::is_ident(name) {
+                        return Err(invalid());
+                    }
+
+                    (OptionNamePart::Simple(name.to_string()), &rest[end..])
+                }
+            };
+
+            parts.push(part);
+
+            if tail.is_empty() {
+                break;
+            }
+
+            rest = tail.strip_prefix('.').ok_or_else(invalid)?;
+        }
+
+        Ok(Self::new(parts))
+    }
+}
+
+impl Display for OptionName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = self
+            .parts
+            .iter()
+            .map(|part| format!("{part}"))
+            .collect::<Vec<String>>()
+            .join(".");
+
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::types::option_field::OptionField;
+
+    use super::*;
+
+    fn parse(source: &str) -> Result<OptionName, ParserError> {
+        let tokens = Lexer::new(source.as_bytes()).tokens().unwrap();
+        OptionName::name_from(&mut TokenStream::from(tokens), "option name")
+    }
+
+    #[test]
+    fn from_ok() {
+        let res = OptionName::try_from("(foo.bar).baz.(.qux.quux)");
+        assert!(res.is_ok(), "option name parse error {:?}", res);
+
+        let name = res.unwrap();
+        assert!(name.is_custom());
+        assert_eq!(
+            name.parts,
+            vec![
+                OptionNamePart::Extension("foo.bar".to_string()),
+                OptionNamePart::Simple("baz".to_string()),
+                OptionNamePart::Extension(".qux.quux".to_string()),
+            ]
+        );
+        assert_eq!(format!("{name}"), "(foo.bar).baz.(.qux.quux)");
+    }
+
+    #[test]
+    fn name_from_ok() {
+        for (source, name) in [
+            ("( foo.bar ) = 1", "(foo.bar)"),
+            ("(foo) .bar = 1", "(foo).bar"),
+            ("(foo).bar.baz", "(foo).bar.baz"),
+            ("(foo) . (.bar) . baz", "(foo).(.bar).baz"),
+            ("foo.bar.(baz)", "foo.bar.(baz)"),
+            ("features . field_presence", "features.field_presence"),
+            ("(message)", "(message)"),
+        ] {
+            let res = parse(source);
+            assert!(res.is_ok(), "parse error {:?} for '{source}'", res);
+            assert_eq!(format!("{}", res.unwrap()), name);
+        }
+    }
+
+    #[test]
+    fn name_from_err() {
+        for source in ["(foo", "(foo.)", "foo.", ".foo", "(foo) .", "= 1", "(1)"] {
+            assert!(parse(source).is_err(), "accepted '{source}'");
+        }
+
+        // The name ends where the parts are no longer separated by dots
+        let tokens = Lexer::new(b"option (foo)bar = 1;").tokens().unwrap();
+        assert!(OptionField::try_from(&mut TokenStream::from(tokens)).is_err());
+    }
+
+    #[test]
+    fn builtin_ok() {
+        let name = OptionName::try_from("features.field_presence").unwrap();
+
+        assert!(name.is_builtin());
+        assert_eq!(name.parts.len(), 2);
+        assert_eq!(name.as_simple(), None);
+        assert_eq!(
+            OptionName::try_from("packed").unwrap().as_simple(),
+            Some("packed")
+        );
+    }
+
+    #[test]
+    fn invalid_err() {
+        for name in ["", "(foo", "foo..bar", "(foo)bar", "1foo", "(foo bar)"] {
+            assert!(OptionName::try_from(name).is_err(), "accepted '{name}'");
+        }
+    }
+}
@@ -0,0 +1,337 @@
+// Option values e.g. `true`, `-1.5`, `"foo"` or the text format message literal of
+// `option (google.api.http) = { get: "/v1/{name=*}" body: "*" };`
+//                              ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+use log::debug;
+use std::fmt::Display;
+
+use crate::error::ParserError;
+use crate::position::Span;
+use crate::token::{Constant, Type};
+use crate::token_stream::TokenStream;
+
+const TEXT_FORMAT_URL: &str = "https://protobuf.dev/reference/protobuf/textformat-spec/";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionValueKind {
+    Constant(Constant),
+    Message(MessageLiteral),
+    // Lists are only allowed as values of message literal fields
+    List(Vec<OptionValue>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptionValue {
+    pub kind: OptionValueKind,
+    pub span: Span,
+}
+
+impl OptionValue {
+    pub fn new(kind: OptionValueKind) -> Self {
+        Self {
+            kind,
+            span: Span::default(),
+        }
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn as_constant(&self) -> Option<&Constant> {
+        match &self.kind {
+            OptionValueKind::Constant(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_message(&self) -> Option<&MessageLiteral> {
+        match &self.kind {
+            OptionValueKind::Message(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    fn is_message_begin(tokens: &TokenStream) -> bool {
+        tokens.peek_eq(Type::LBrace) || tokens.peek_eq(Type::LT)
+    }
+
+    // Pops a single value, the remaining tokens are left for the caller.
+    pub(crate) fn value_from(tokens: &mut TokenStream) -> Result<Self, ParserError> {
+        if Self::is_message_begin(tokens) {
+            return Self::message_from(tokens);
+        }
+
+        if tokens.peek_eq(Type::LBrack) {
+            return Self::list_from(tokens);
+        }
+
+        Self::constant_from(tokens)
+    }
+
+    // Pops a single, possibly signed, constant.
+    pub(crate) fn constant_from(tokens: &mut TokenStream) -> Result<Self, ParserError> {
+        let start = tokens.mark();
+
+        let mut res = Self::from(tokens.constant_as_typed("option value")?);
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
+    }
+
+    pub(crate) fn message_from(tokens: &mut TokenStream) -> Result<Self, ParserError> {
+        let start = tokens.mark();
+
+        let mut res = Self::from(MessageLiteral::message_from(tokens)?);
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
+    }
+
+    fn list_from(tokens: &mut TokenStream) -> Result<Self, ParserError> {
+        let start = tokens.mark();
+        let mut values = vec![];
+
+        tokens.next_eq(Type::LBrack, "list opening bracket('[')")?;
+
+        while !tokens.peek_eq(Type::RBrack) {
+            values.push(Self::value_from(tokens)?);
+
+            if tokens.peek_eq(Type::RBrack) {
+                break;
+            }
+
+            tokens.next_eq(Type::Comma, "list delimiter(',')")?;
+        }
+
+        tokens.next_eq(Type::RBrack, "list closing bracket(']')")?;
+
+        let mut res = Self::new(OptionValueKind::List(values));
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
+    }
+}
+
+impl From<Constant> for OptionValue {
+    fn from(value: Constant) -> Self {
+        Self::new(OptionValueKind::Constant(value))
+    }
+}
+
+impl From<MessageLiteral> for OptionValue {
+    fn from(value: MessageLiteral) -> Self {
+        Self::new(OptionValueKind::Message(value))
+    }
+}
+
+impl TryFrom<&mut TokenStream> for OptionValue {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("option value({:?})", tokens.peek());
+
+        Self::value_from(tokens)
+    }
+}
+
+impl Display for OptionValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            OptionValueKind::Constant(v) => write!(f, "{v}"),
+            OptionValueKind::Message(v) => write!(f, "{v}"),
+            OptionValueKind::List(v) => {
+                let values = v
+                    .iter()
+                    .map(|value| format!("{value}"))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+
+                write!(f, "[{values}]")
+            }
+        }
+    }
+}
+
+// Message literal
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MessageLiteral {
+    pub fields: Vec<(String, OptionValue)>,
+}
+
+impl MessageLiteral {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(&mut self, field: (String, OptionValue)) {
+        self.fields.push(field);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&OptionValue> {
+        self.fields.iter().find(|(k, _)| k == name).map(|(_, v)| v)
+    }
+
+    // Extension and Any field names are enclosed in brackets e.g. `[foo.bar]` or
+    // `[type.googleapis.com/foo.Bar]`
+    fn name_from(tokens: &mut TokenStream) -> Result<String, ParserError> {
+        if !tokens.peek_eq(Type::LBrack) {
+            return tokens.fullident_as_string("message literal field name");
+        }
+
+        tokens.next_eq(Type::LBrack, "extension name opening bracket('[')")?;
+
+        let mut name = String::new();
+        while !tokens.peek_eq(Type::RBrack) {
+            match tokens.pop() {
+                Some(token) => name.push_str(&format!("{}", token.typ())),
+                None => return Err(tokens.eof("extension name closing bracket(']')")),
+            }
+        }
+
+        tokens.next_eq(Type::RBrack, "extension name closing bracket(']')")?;
+
+        Ok(format!("[{name}]"))
+    }
+
+    // Pops a message literal e.g. `{ foo: 1 bar { baz: "qux" } }`.
+    pub(crate) fn message_from(tokens: &mut TokenStream) -> Result<Self, ParserError> {
+        let close = match tokens
+            .next_contains(&[Type::LBrace, Type::LT], "message literal opening")?
+            .typ()
+        {
+            Type::LT => Type::GT,
+            _ => Type::RBrace,
+        };
+
+        let mut res = Self::new();
+
+        while !tokens.peek_eq(close.clone()) {
+            let name = Self::name_from(tokens)?;
+
+            // The colon is optional for message values and lists of messages
+            let value = if tokens.peek_eq(Type::Colon) {
+                tokens.next_eq(Type::Colon, "message literal field delimiter(':')")?;
+                OptionValue::value_from(tokens)?
+            } else if OptionValue::is_message_begin(tokens) || tokens.peek_eq(Type::LBrack) {
+                OptionValue::value_from(tokens)?
+            } else {
+                return Err(tokens.unexpected(&format!(
+                    "message literal field delimiter(':'), check {TEXT_FORMAT_URL}"
+                )));
+            };
+
+            res.push((name, value));
+
+            // Fields are optionally separated by either a comma or a semicolon
+            if tokens.peek_eq(Type::Comma) || tokens.peek_eq(Type::Semicolon) {
+                tokens.pop();
+            }
+        }
+
+        tokens.next_eq(close, "message literal closing")?;
+
+        Ok(res)
+    }
+}
+
+impl Display for MessageLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.fields.is_empty() {
+            return write!(f, "{{}}");
+        }
+
+        let fields = self
+            .fields
+            .iter()
+            .map(|(k, v)| match v.kind {
+                OptionValueKind::Message(_) => format!("{k} {v}"),
+                _ => format!("{k}: {v}"),
+            })
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        write!(f, "{{ {fields} }}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::token::StringLit;
+    use crate::token_stream::stream;
+
+    use super::*;
+
+    #[test]
+    fn message_ok() {
+        let mut ts = stream(&[
+            Type::LBrace,
+            Type::Ident("get".to_string()),
+            Type::Colon,
+            Type::StringLit(StringLit::new(b"/v1".to_vec())),
+            Type::Ident("additional_bindings".to_string()),
+            Type::LBrace,
+            Type::Ident("post".to_string()),
+            Type::Colon,
+            Type::StringLit(StringLit::new(b"/v2".to_vec())),
+            Type::RBrace,
+            Type::Semicolon,
+            Type::Ident("ids".to_string()),
+            Type::Colon,
+            Type::LBrack,
+            Type::IntLit(1),
+            Type::Comma,
+            Type::IntLit(2),
+            Type::RBrack,
+            Type::Comma,
+            Type::LBrack,
+            Type::FullIdent("foo.ext".to_string()),
+            Type::RBrack,
+            Type::Colon,
+            Type::BoolLit(true),
+            Type::RBrace,
+        ]);
+
+        let res = OptionValue::try_from(&mut ts);
+        assert!(res.is_ok(), "option value parse error {:?}", res);
+
+        let value = res.unwrap();
+        let message = value.as_message().unwrap();
+        assert_eq!(message.fields.len(), 4);
+        assert_eq!(
+            message.get("get"),
+            Some(&OptionValue::from(Constant::String(StringLit::new(
+                b"/v1".to_vec()
+            ))))
+        );
+        assert_eq!(
+            format!("{value}"),
+            "{ get: \"/v1\" additional_bindings { post: \"/v2\" } ids: [1, 2] [foo.ext]: true }"
+        );
+    }
+
+    #[test]
+    fn missing_colon_err() {
+        let mut ts = stream(&[
+            Type::LBrace,
+            Type::Ident("get".to_string()),
+            Type::StringLit(StringLit::new(b"/v1".to_vec())),
+            Type::RBrace,
+        ]);
+
+        assert!(OptionValue::try_from(&mut ts).is_err());
+    }
+
+    #[test]
+    fn any_url_ok() {
+        let source = "{ [type.googleapis.com/foo.Bar] { baz: 1 } }";
+        let tokens = Lexer::new(source.as_bytes()).tokens().unwrap();
+        let mut ts = TokenStream::from(tokens);
+
+        let value = OptionValue::try_from(&mut ts).unwrap();
+        let message = value.as_message().unwrap();
+        assert!(message.get("[type.googleapis.com/foo.Bar]").is_some());
+        assert_eq!(format!("{value}"), source);
+    }
+}
@@ -3,31 +3,44 @@ use std::fmt::Display;
 
 use crate::error::ParserError;
 use crate::indent::indent;
+use crate::position::Span;
 use crate::token::Type;
 use crate::token_stream::TokenStream;
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Package {
     pub value: String,
+    pub span: Span,
 }
 
 impl Package {
     pub fn new(value: String) -> Self {
-        Package { value }
+        Package {
+            value,
+            span: Span::default(),
+        }
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
     }
 }
 
-impl TryFrom<TokenStream> for Package {
+impl TryFrom<&mut TokenStream> for Package {
     type Error = ParserError;
 
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("package({:?})", &tokens);
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("package({:?})", tokens.peek());
+        let start = tokens.mark();
 
-        tokens.next_eq(Type::Semicolon, "package line ending(';')")?;
-        let value = tokens.fullident_as_string("package value")?;
         tokens.next_eq(Type::Package, "package identifier")?;
+        let value = tokens.fullident_as_string("package value")?;
+        tokens.next_eq(Type::Semicolon, "package line ending(';')")?;
+
+        let mut res = Self::new(value);
+        res.set_span(tokens.span_from(start));
 
-        Ok(Self::new(value))
+        Ok(res)
     }
 }
 
@@ -1,10 +1,17 @@
 use log::debug;
 use std::fmt::Display;
 
+use crate::cst::Cst;
 use crate::error::ParserError;
+use crate::features::ResolvedFeatures;
+use crate::position::Span;
+use crate::scope::Scope;
 use crate::token::Type;
 use crate::token_stream::TokenStream;
+use crate::types::edition::Edition;
+use crate::types::empty::Empty;
 use crate::types::enumerate::Enum;
+use crate::types::extend::Extend;
 use crate::types::import::Import;
 use crate::types::message::Message;
 use crate::types::option_field::OptionField;
@@ -17,7 +24,9 @@ use super::comment::{BlockComment, LineComment};
 // TODO From for ProtoMember should probably be generated by macro
 #[derive(Clone, Debug, PartialEq)]
 pub enum ProtoMember {
+    Empty(Empty),
     Enum(Enum),
+    Extend(Extend),
     Import(Import),
     Message(Message),
     Option(OptionField),
@@ -27,12 +36,24 @@ pub enum ProtoMember {
     BlockComment(BlockComment),
 }
 
+impl From<Empty> for ProtoMember {
+    fn from(value: Empty) -> Self {
+        Self::Empty(value)
+    }
+}
+
 impl From<Enum> for ProtoMember {
     fn from(value: Enum) -> Self {
         Self::Enum(value)
     }
 }
 
+impl From<Extend> for ProtoMember {
+    fn from(value: Extend) -> Self {
+        Self::Extend(value)
+    }
+}
+
 impl From<Import> for ProtoMember {
     fn from(value: Import) -> Self {
         Self::Import(value)
@@ -78,7 +99,9 @@ impl From<LineComment> for ProtoMember {
 impl Display for ProtoMember {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Self::Empty(v) => write!(f, "{}", v),
             Self::Enum(v) => write!(f, "{}", v),
+            Self::Extend(v) => write!(f, "{}", v),
             Self::Import(v) => write!(f, "{}", v),
             Self::Message(v) => write!(f, "{}", v),
             Self::Option(v) => write!(f, "{}", v),
@@ -92,9 +115,16 @@ impl Display for ProtoMember {
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Proto {
-    syntax: Syntax,
-    package: Package,
+    // File name e.g. `foo/bar.proto`, empty when parsed from memory without one
+    name: String,
+    // `None` for files without a syntax statement which are proto2
+    syntax: Option<Syntax>,
+    edition: Option<Edition>,
+    package: Option<Package>,
     members: Vec<ProtoMember>,
+    span: Span,
+    // Tokens the file was parsed from, `None` for files built in code
+    cst: Option<Cst>,
 }
 
 impl Proto {
@@ -102,96 +132,398 @@ impl Proto {
         Self::default()
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn cst(&self) -> Option<&Cst> {
+        self.cst.as_ref()
+    }
+
+    pub fn set_cst(&mut self, cst: Cst) {
+        self.cst = Some(cst);
+    }
+
+    // Exact source of a node e.g. `proto.text(message.span)` including the comments and spacing
+    // inside of the message.
+    pub fn text(&self, span: Span) -> Option<String> {
+        self.cst.as_ref().map(|cst| cst.text(span))
+    }
+
     pub fn push(&mut self, member: ProtoMember) {
         self.members.push(member);
     }
 
     pub fn set_package(&mut self, p: Package) {
-        self.package = p;
+        self.package = Some(p);
     }
 
     pub fn set_syntax(&mut self, s: Syntax) {
-        self.syntax = s;
+        self.syntax = Some(s);
     }
-}
 
-impl TryFrom<TokenStream> for Proto {
-    type Error = ParserError;
+    pub fn set_edition(&mut self, e: Edition) {
+        self.edition = Some(e);
+    }
 
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("proto({:?})", tokens);
+    pub fn syntax(&self) -> Option<&Syntax> {
+        self.syntax.as_ref()
+    }
 
-        let mut proto = Self::new();
+    pub fn edition(&self) -> Option<&Edition> {
+        self.edition.as_ref()
+    }
 
-        while !tokens.is_empty() {
-            let peek_token = match tokens.peek() {
-                Some(v) => v,
-                None => {
-                    return Err(ParserError::Syntax(
-                        "protobuf member".to_string(),
-                        "nothing".to_string(),
-                    ))
-                }
-            };
-
-            // Tokens are order according to expected frequency
-            match peek_token.typ() {
-                Type::Message => {
-                    let block = tokens.select_block(Type::LBrace, Type::RBrace);
-                    let message = ProtoMember::from(Message::try_from(block)?);
-                    proto.push(ProtoMember::from(message));
-                }
-                Type::Service => {
-                    let block = tokens.select_block(Type::LBrace, Type::RBrace);
-                    let service = Service::try_from(block)?;
-                    proto.push(ProtoMember::from(service));
-                }
-                Type::Enum => {
-                    let block = tokens.select_block(Type::LBrace, Type::RBrace);
-                    let enm = Enum::try_from(block)?;
-                    proto.push(ProtoMember::from(enm));
-                }
-                Type::Option => {
-                    let line = tokens.select_until(Type::Semicolon);
-                    let option = OptionField::try_from(line)?;
-                    proto.push(ProtoMember::from(option));
-                }
-                Type::Import => {
-                    let line = tokens.select_until(Type::Semicolon);
-                    let import = Import::try_from(line)?;
-                    proto.push(ProtoMember::from(import));
-                }
-                Type::Syntax => {
-                    let line = tokens.select_until(Type::Semicolon);
-                    let syntax = Syntax::try_from(line)?;
-                    proto.set_syntax(syntax);
+    pub fn package(&self) -> Option<&Package> {
+        self.package.as_ref()
+    }
+
+    pub fn members(&self) -> &[ProtoMember] {
+        &self.members
+    }
+
+    pub fn imports(&self) -> Vec<&Import> {
+        self.members
+            .iter()
+            .filter_map(|m| match m {
+                ProtoMember::Import(v) => Some(v),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Effective editions features of every element in the file
+    pub fn features(&self) -> Result<ResolvedFeatures, ParserError> {
+        ResolvedFeatures::try_from(self)
+    }
+
+    // File level statements e.g. `syntax` can only appear once, the first one is kept. Types
+    // defined twice are reported but kept.
+    fn check_unique(name: &str, previous: Option<Span>, span: Span) -> Result<(), ParserError> {
+        match previous {
+            None => Ok(()),
+            Some(previous) => Err(ParserError::DuplicateDefinition {
+                name: name.to_string(),
+                span,
+                previous,
+            }),
+        }
+    }
+
+    // A file declares either a syntax or an edition.
+    fn check_exclusive(name: &str, other: bool, span: Span) -> Result<(), ParserError> {
+        match other {
+            false => Ok(()),
+            true => Err(ParserError::expected("either a syntax or an edition statement", name)
+                .with_span(span)),
+        }
+    }
+
+    fn push_member(
+        &mut self,
+        tokens: &mut TokenStream,
+        scope: &mut Scope,
+    ) -> Result<(), ParserError> {
+        let typ = match tokens.peek() {
+            Some(token) => token.typ(),
+            None => return Err(tokens.eof("protobuf member")),
+        };
+
+        // Tokens are order according to expected frequency
+        match typ {
+            Type::Message => {
+                let message = Message::try_from(&mut *tokens)?;
+                if let Err(e) = scope.define(&message.name, message.span) {
+                    tokens.report(e);
                 }
-                Type::Package => {
-                    let line = tokens.select_until(Type::Semicolon);
-                    let package = Package::try_from(line)?;
-                    proto.set_package(package);
+                self.push(ProtoMember::from(message));
+            }
+            Type::Service => {
+                let service = Service::try_from(&mut *tokens)?;
+                if let Err(e) = scope.define(&service.name, service.span) {
+                    tokens.report(e);
                 }
-                Type::Slash => {
-                    if tokens.is_line_comment() {
-                        let line = tokens.select_line_comment()?;
-                        let comment = LineComment::from(line);
-                        proto.push(ProtoMember::from(comment));
-                    }
-                    if tokens.is_block_comment() {
-                        let block = tokens.select_block_comment()?;
-                        let comment = BlockComment::from(block);
-                        proto.push(ProtoMember::from(comment));
-                    }
+                self.push(ProtoMember::from(service));
+            }
+            Type::Enum => {
+                let enm = Enum::try_from(&mut *tokens)?;
+                if let Err(e) = scope.define(&enm.name, enm.span) {
+                    tokens.report(e);
                 }
-                invalid => {
-                    return Err(ParserError::Syntax(
-                        "protobuf member".to_string(),
-                        format!("{invalid}"),
-                    ))
+                self.push(ProtoMember::from(enm));
+            }
+            Type::Extend => {
+                let extend = Extend::try_from(tokens)?;
+                self.push(ProtoMember::from(extend));
+            }
+            Type::Option => {
+                let option = OptionField::try_from(tokens)?;
+                self.push(ProtoMember::from(option));
+            }
+            Type::Import => {
+                let import = Import::try_from(tokens)?;
+                self.push(ProtoMember::from(import));
+            }
+            Type::Syntax => {
+                let syntax = Syntax::try_from(tokens)?;
+                let previous = self.syntax.as_ref().map(|s| s.span);
+                Self::check_unique("syntax", previous, syntax.span)?;
+                Self::check_exclusive("syntax", self.edition.is_some(), syntax.span)?;
+                self.set_syntax(syntax);
+            }
+            Type::Edition => {
+                let edition = Edition::try_from(&mut *tokens)?;
+                let previous = self.edition.as_ref().map(|e| e.span);
+                Self::check_unique("edition", previous, edition.span)?;
+                Self::check_exclusive("edition", self.syntax.is_some(), edition.span)?;
+                self.set_edition(edition);
+                tokens.set_editions(true);
+            }
+            Type::Package => {
+                let package = Package::try_from(tokens)?;
+                let previous = self.package.as_ref().map(|p| p.span);
+                Self::check_unique("package", previous, package.span)?;
+                self.set_package(package);
+            }
+            Type::Semicolon => self.push(ProtoMember::from(Empty::try_from(tokens)?)),
+            Type::LineComment(_) => {
+                let comment = LineComment::try_from(tokens)?;
+                self.push(ProtoMember::from(comment));
+            }
+            Type::BlockComment(_) => {
+                let comment = BlockComment::try_from(tokens)?;
+                self.push(ProtoMember::from(comment));
+            }
+            _ => return Err(tokens.unexpected("protobuf member")),
+        }
+
+        Ok(())
+    }
+}
+
+// Top level statements failing to parse are reported and skipped, the tokens' diagnostics hold
+// every problem found.
+impl From<&mut TokenStream> for Proto {
+    fn from(tokens: &mut TokenStream) -> Self {
+        debug!("proto({:?})", tokens.peek());
+
+        let mut proto = Self::new();
+        let mut scope = Scope::new();
+
+        while !tokens.is_empty() {
+            let start = tokens.mark();
+
+            if let Err(e) = proto.push_member(tokens, &mut scope) {
+                tokens.recover(start, e);
+            }
+        }
+
+        proto.set_span(tokens.span());
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::enumerate::EnumMember;
+    use crate::types::message::MessageMember;
+    use crate::types::reserved::ReservedKind;
+
+    use super::*;
+
+    #[test]
+    fn recover_ok() {
+        let source = r#"syntax = "proto3";
+message A {
+  string a = ;
+  int32 b = 2;
+  option (x) = { a: 1 b };
+  int32 c = 3
+}
+mesage B {}
+enum C { X = 0; Y }
+service S { rpc F (A) returns (A); foo; }
+"#;
+        let mut tokens = Cst::try_from(source).unwrap().token_stream();
+        let proto = Proto::from(&mut tokens);
+        let diagnostics = tokens.take_diagnostics();
+
+        assert_eq!(diagnostics.len(), 6, "{:?}", diagnostics);
+        assert_eq!(format!("{}", diagnostics[0].span()), "3:14 -> 3:15");
+        assert_eq!(
+            diagnostics.iter().map(|d| d.span().start().line()).collect::<Vec<usize>>(),
+            vec![3, 5, 7, 8, 9, 10]
+        );
+
+        assert!(proto.syntax().is_some_and(Syntax::is_proto3));
+        match proto.members() {
+            [ProtoMember::Message(a), ProtoMember::Enum(c), ProtoMember::Service(s)] => {
+                assert_eq!(a.members.len(), 1);
+                assert_eq!(c.members.len(), 1);
+                assert_eq!(s.members.len(), 1);
+            }
+            invalid => panic!("unexpected members {:?}", invalid),
+        }
+    }
+
+    #[test]
+    fn duplicate_err() {
+        let source = r#"syntax = "proto3";
+syntax = "proto2";
+message A {
+  int32 a = 1;
+  oneof b { string a = 2; }
+}
+enum E { X = 0; X = 1; }
+message A {}
+"#;
+        let mut tokens = Cst::try_from(source).unwrap().token_stream();
+        let proto = Proto::from(&mut tokens);
+        let diagnostics = tokens.take_diagnostics();
+
+        assert!(diagnostics.iter().all(|d| d.error().code() == "E0007"), "{:?}", diagnostics);
+        assert_eq!(
+            diagnostics.iter().map(|d| d.span().start().line()).collect::<Vec<usize>>(),
+            vec![2, 5, 7, 8]
+        );
+
+        match diagnostics[1].error() {
+            ParserError::DuplicateDefinition { name, previous, .. } => {
+                assert_eq!(name, "a");
+                assert_eq!(format!("{previous}"), "4:3 -> 4:15");
+            }
+            invalid => panic!("unexpected error {:?}", invalid),
+        }
+
+        assert!(proto.syntax().is_some_and(Syntax::is_proto3));
+        assert_eq!(proto.members().len(), 3);
+    }
+
+    #[test]
+    fn editions_reserved_ok() {
+        let source = "edition = \"2023\";\nmessage A { reserved foo; }\nenum E { reserved \"X\"; }";
+        let mut tokens = Cst::try_from(source).unwrap().token_stream();
+        let proto = Proto::from(&mut tokens);
+        let diagnostics = tokens.take_diagnostics();
+
+        // Only the string literal of the enum is rejected
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].span().start().line(), 3);
+        match proto.members() {
+            [ProtoMember::Message(a), ..] => {
+                let kind = match &a.members[..] {
+                    [MessageMember::Reserved(v)] => &v.kind,
+                    invalid => panic!("unexpected members {:?}", invalid),
+                };
+                assert!(matches!(kind, ReservedKind::Idents(_)))
+            }
+            invalid => panic!("unexpected members {:?}", invalid),
+        }
+    }
+
+    #[test]
+    fn text_ok() {
+        let source = "syntax = \"proto3\";\n\n// A\nmessage A {\n  string  a = 1; // a\n\n  \
+                      int32 b = 2;\n}\n";
+        let proto = crate::parse_str("a.proto", source).unwrap();
+
+        let message = match proto.members() {
+            [ProtoMember::LineComment(_), ProtoMember::Message(v)] => v,
+            invalid => panic!("unexpected members {:?}", invalid),
+        };
+        let text = proto.text(message.span).unwrap();
+        assert!(text.starts_with("message A {\n  string  a = 1; // a\n\n"), "{text}");
+
+        // Only the reprinted field changes, spacing and comments elsewhere are kept
+        let field = match &message.members[..] {
+            [MessageMember::Field(v), ..] => v,
+            invalid => panic!("unexpected members {:?}", invalid),
+        };
+        let mut edited = field.clone();
+        edited.name = "c".to_string();
+        let edits = [(field.span, format!("{edited}").trim().to_string())];
+        assert_eq!(
+            proto.cst().unwrap().replace(&edits).unwrap(),
+            source.replace("string  a", "string c")
+        );
+
+        assert_eq!(Proto::new().text(message.span), None);
+    }
+
+    #[test]
+    fn bool_ident_ok() {
+        let source = "message A { bool true = 1; }\nenum E { true = 0; false = 1; }";
+        let mut tokens = Cst::try_from(source).unwrap().token_stream();
+        let proto = Proto::from(&mut tokens);
+
+        assert!(tokens.take_diagnostics().is_empty());
+        match proto.members() {
+            [ProtoMember::Message(a), ProtoMember::Enum(e)] => {
+                assert!(matches!(&a.members[..], [MessageMember::Field(f)] if f.name == "true"));
+                let names: Vec<&str> = e
+                    .members
+                    .iter()
+                    .filter_map(|m| match m {
+                        EnumMember::Field(v) => Some(v.name.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                assert_eq!(names, ["true", "false"]);
+            }
+            invalid => panic!("unexpected members {:?}", invalid),
+        }
+    }
+
+    #[test]
+    fn group_type_ok() {
+        // `group` is only a keyword when followed by a name, a number and a body
+        let sources = [
+            "syntax = \"proto2\";\nmessage group {}\nmessage A {\n  optional group g = 1;\n  \
+             oneof o { group h = 2; }\n  repeated group Result = 3 [deprecated = true] {}\n}",
+            "syntax = \"proto3\";\nmessage group {}\nmessage A { group g = 1; }",
+        ];
+
+        for source in sources {
+            let mut tokens = Cst::try_from(source).unwrap().token_stream();
+            let proto = Proto::from(&mut tokens);
+            let diagnostics = tokens.take_diagnostics();
+            assert!(diagnostics.is_empty(), "{source}: {diagnostics:?}");
+
+            match proto.members() {
+                [ProtoMember::Message(_), ProtoMember::Message(a)] => {
+                    assert!(matches!(&a.members[0], MessageMember::Field(f) if f.name == "g"))
                 }
+                invalid => panic!("unexpected members {:?}", invalid),
             }
         }
+    }
+
+    #[test]
+    fn syntax_edition_err() {
+        for source in [
+            "syntax = \"proto3\";\nedition = \"2023\";",
+            "edition = \"2023\";\nsyntax = \"proto3\";",
+        ] {
+            let mut tokens = Cst::try_from(source).unwrap().token_stream();
+            let _ = Proto::from(&mut tokens);
+            let diagnostics = tokens.take_diagnostics();
 
-        return Ok(proto);
+            assert_eq!(diagnostics.len(), 1, "{source}: {diagnostics:?}");
+            assert_eq!(diagnostics[0].error().code(), "E0005");
+            assert_eq!(diagnostics[0].span().start().line(), 2);
+        }
     }
 }
@@ -0,0 +1,348 @@
+use log::debug;
+use std::fmt::Display;
+
+use crate::error::ParserError;
+use crate::indent::indent;
+use crate::position::Span;
+use crate::token::{StringLit, Type};
+use crate::token_stream::TokenStream;
+
+// Reserved e.g. `reserved 2, 15, 9 to 11, 40 to max;` or `reserved "foo", "bar";`, files using
+// editions write reserved names as identifiers e.g. `reserved foo, bar;`
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RangeEnd {
+    Value(i32),
+    Max,
+}
+
+impl Display for RangeEnd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Value(v) => write!(f, "{v}"),
+            Self::Max => write!(f, "max"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberRange {
+    pub start: i32,
+    pub end: Option<RangeEnd>,
+}
+
+impl NumberRange {
+    pub fn new(start: i32, end: Option<RangeEnd>) -> Self {
+        Self { start, end }
+    }
+}
+
+impl Display for NumberRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.end {
+            Some(end) => write!(f, "{} to {end}", self.start),
+            None => write!(f, "{}", self.start),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReservedKind {
+    Ranges(Vec<NumberRange>),
+    // Literals are kept as written so escapes are printed back unchanged
+    Names(Vec<StringLit>),
+    // Names written as identifiers, editions only
+    Idents(Vec<String>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reserved {
+    pub kind: ReservedKind,
+    pub span: Span,
+}
+
+impl Reserved {
+    pub fn new(kind: ReservedKind) -> Self {
+        Self {
+            kind,
+            span: Span::default(),
+        }
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    // Pops a comma separated list of ranges e.g. `2, 15, 9 to 11`.
+    pub(crate) fn ranges_from(tokens: &mut TokenStream) -> Result<Vec<NumberRange>, ParserError> {
+        let to = Type::Ident("to".to_string());
+        let mut ranges = vec![];
+
+        loop {
+            let start = Self::value_from(tokens, "range start(intLit)")?;
+
+            let range = match tokens.peek_eq(to.clone()) {
+                true => {
+                    tokens.next_eq(to.clone(), "range delimiter('to')")?;
+
+                    let end = match tokens.peek_eq(Type::Ident("max".to_string())) {
+                        true => {
+                            tokens.pop();
+                            RangeEnd::Max
+                        }
+                        false => {
+                            RangeEnd::Value(Self::value_from(tokens, "range end(intLit, max)")?)
+                        }
+                    };
+
+                    NumberRange::new(start, Some(end))
+                }
+                false => NumberRange::new(start, None),
+            };
+
+            ranges.push(range);
+
+            if !tokens.peek_eq(Type::Comma) {
+                break;
+            }
+
+            tokens.next_eq(Type::Comma, "range delimiter(',')")?;
+        }
+
+        Ok(ranges)
+    }
+
+    // Pops a possibly negative range value, enum ranges may be negative e.g. `-5 to -1`.
+    fn value_from(tokens: &mut TokenStream, expect_msg: &str) -> Result<i32, ParserError> {
+        let value = tokens.constant_as_typed(expect_msg)?.as_int_in(
+            i32::MIN.into(),
+            i32::MAX.into(),
+            expect_msg,
+        )?;
+
+        Ok(value as i32)
+    }
+
+    fn names_from(tokens: &mut TokenStream) -> Result<Vec<StringLit>, ParserError> {
+        let mut names = vec![];
+
+        loop {
+            let name = tokens.strlit("reserved name")?;
+            if name.as_str().is_none() {
+                return Err(ParserError::invalid(
+                    "reserved name to be valid UTF-8",
+                    name.raw(),
+                ));
+            }
+            names.push(name);
+
+            if !tokens.peek_eq(Type::Comma) {
+                break;
+            }
+
+            tokens.next_eq(Type::Comma, "reserved name delimiter(',')")?;
+        }
+
+        Ok(names)
+    }
+
+    // Files using editions write reserved names as identifiers.
+    fn idents_from(tokens: &mut TokenStream) -> Result<Vec<String>, ParserError> {
+        let mut names = vec![];
+
+        loop {
+            let is_strlit = matches!(tokens.peek().map(|t| t.typ()), Some(Type::StringLit(_)));
+            if is_strlit {
+                return Err(tokens.unexpected("reserved name(ident), not strLit in editions"));
+            }
+
+            names.push(tokens.ident_as_string("reserved name(ident)")?);
+
+            if !tokens.peek_eq(Type::Comma) {
+                break;
+            }
+
+            tokens.next_eq(Type::Comma, "reserved name delimiter(',')")?;
+        }
+
+        Ok(names)
+    }
+}
+
+impl TryFrom<&mut TokenStream> for Reserved {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("reserved({:?})", tokens.peek());
+        let start = tokens.mark();
+
+        tokens.next_eq(Type::Reserved, "reserved identifier")?;
+
+        // Enum ranges may start with a negative value
+        let is_range = matches!(
+            tokens.peek().map(|t| t.typ()),
+            Some(Type::IntLit(_) | Type::Minus)
+        );
+
+        let kind = match is_range {
+            true => ReservedKind::Ranges(Self::ranges_from(tokens)?),
+            false if tokens.is_editions() => ReservedKind::Idents(Self::idents_from(tokens)?),
+            false => ReservedKind::Names(Self::names_from(tokens)?),
+        };
+
+        tokens.next_eq(Type::Semicolon, "reserved line ending(';')")?;
+
+        let mut res = Self::new(kind);
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
+    }
+}
+
+impl Display for Reserved {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        indent(f)?;
+
+        let values = match &self.kind {
+            ReservedKind::Ranges(v) => v
+                .iter()
+                .map(|range| format!("{range}"))
+                .collect::<Vec<String>>(),
+            ReservedKind::Names(v) => v
+                .iter()
+                .map(|name| format!("{name}"))
+                .collect::<Vec<String>>(),
+            ReservedKind::Idents(v) => v.clone(),
+        };
+
+        writeln!(f, "reserved {};", values.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::token::StringLit;
+    use crate::token_stream::stream;
+
+    use super::*;
+
+    #[test]
+    fn ranges_ok() {
+        let mut ts = stream(&[
+            Type::Reserved,
+            Type::IntLit(2),
+            Type::Comma,
+            Type::IntLit(9),
+            Type::Ident("to".to_string()),
+            Type::IntLit(11),
+            Type::Comma,
+            Type::IntLit(40),
+            Type::Ident("to".to_string()),
+            Type::Ident("max".to_string()),
+            Type::Semicolon,
+        ]);
+
+        let res = Reserved::try_from(&mut ts);
+        assert!(res.is_ok(), "reserved parse error {:?}", res);
+
+        let reserved = res.unwrap();
+        assert_eq!(
+            reserved,
+            Reserved::new(ReservedKind::Ranges(vec![
+                NumberRange::new(2, None),
+                NumberRange::new(9, Some(RangeEnd::Value(11))),
+                NumberRange::new(40, Some(RangeEnd::Max)),
+            ]))
+        );
+        assert_eq!(format!("{reserved}"), "reserved 2, 9 to 11, 40 to max;\n");
+    }
+
+    #[test]
+    fn names_ok() {
+        let mut ts = stream(&[
+            Type::Reserved,
+            Type::StringLit(StringLit::new(b"foo".to_vec())),
+            Type::Comma,
+            Type::StringLit(StringLit::new(b"bar".to_vec())),
+            Type::Semicolon,
+        ]);
+
+        let res = Reserved::try_from(&mut ts);
+        assert!(res.is_ok(), "reserved parse error {:?}", res);
+
+        let reserved = res.unwrap();
+        assert_eq!(
+            reserved,
+            Reserved::new(ReservedKind::Names(vec![
+                StringLit::new(b"foo".to_vec()),
+                StringLit::new(b"bar".to_vec())
+            ]))
+        );
+        assert_eq!(format!("{reserved}"), "reserved \"foo\", \"bar\";\n");
+    }
+
+    #[test]
+    fn names_escape_ok() {
+        let source = r#"reserved "a\"b", 'c';"#;
+        let tokens = Lexer::new(source.as_bytes()).tokens().unwrap();
+
+        let reserved = Reserved::try_from(&mut TokenStream::from(tokens)).unwrap();
+        match &reserved.kind {
+            ReservedKind::Names(v) => assert_eq!(v[0].as_str(), Some("a\"b")),
+            invalid => panic!("expected reserved names, got {invalid:?}"),
+        }
+
+        // Printed as written and parsed back to the same names
+        let printed = format!("{reserved}");
+        assert_eq!(printed, format!("{source}\n"));
+        let tokens = Lexer::new(printed.as_bytes()).tokens().unwrap();
+        assert_eq!(
+            Reserved::try_from(&mut TokenStream::from(tokens)).unwrap(),
+            reserved
+        );
+    }
+
+    #[test]
+    fn mixed_err() {
+        let mut ts = stream(&[
+            Type::Reserved,
+            Type::StringLit(StringLit::new(b"foo".to_vec())),
+            Type::Comma,
+            Type::IntLit(2),
+            Type::Semicolon,
+        ]);
+
+        assert!(Reserved::try_from(&mut ts).is_err());
+    }
+
+    #[test]
+    fn editions_names_ok() {
+        let tokens = Lexer::new(b"reserved foo, bar;").tokens().unwrap();
+        let mut ts = TokenStream::from(tokens);
+        ts.set_editions(true);
+
+        let reserved = Reserved::try_from(&mut ts).unwrap();
+        assert_eq!(
+            reserved.kind,
+            ReservedKind::Idents(vec!["foo".to_string(), "bar".to_string()])
+        );
+        assert_eq!(reserved.span.len(), 18);
+        assert_eq!(format!("{reserved}"), "reserved foo, bar;\n");
+    }
+
+    #[test]
+    fn editions_names_err() {
+        // String literals are only allowed with `syntax`, identifiers only with editions
+        for (source, editions) in [("reserved \"foo\";", true), ("reserved foo;", false)] {
+            let tokens = Lexer::new(source.as_bytes()).tokens().unwrap();
+            let mut ts = TokenStream::from(tokens);
+            ts.set_editions(editions);
+
+            match Reserved::try_from(&mut ts) {
+                Err(e @ ParserError::UnexpectedToken { .. }) => assert_eq!(e.code(), "E0005"),
+                res => panic!("expected an unexpected token error, got {res:?}"),
+            }
+        }
+    }
+}
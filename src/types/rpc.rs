@@ -1,97 +1,218 @@
-use std::fmt::Display;
 use log::debug;
+use std::fmt::Display;
 
 use crate::error::ParserError;
-use crate::indent::indent;
+use crate::indent::{indent, level};
+use crate::position::Span;
 use crate::token::Type;
 use crate::token_stream::TokenStream;
-use crate::types::rpc_option::RpcOption;
+use crate::types::comment::{BlockComment, LineComment};
+use crate::types::empty::Empty;
+use crate::types::option_field::OptionField;
+use crate::types::type_ref::TypeRef;
+
+// Rpc e.g. `rpc Foo (Bar) returns (stream Baz);` or with a body of options
+// `rpc Foo (Bar) returns (Baz) { option deprecated = true; }`
+// https://protobuf.com/docs/language-spec#methods
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RpcMember {
+    BlockComment(BlockComment),
+    Empty(Empty),
+    LineComment(LineComment),
+    Option(OptionField),
+}
+
+impl From<Empty> for RpcMember {
+    fn from(value: Empty) -> Self {
+        Self::Empty(value)
+    }
+}
+
+impl From<BlockComment> for RpcMember {
+    fn from(value: BlockComment) -> Self {
+        RpcMember::BlockComment(value)
+    }
+}
+
+impl From<LineComment> for RpcMember {
+    fn from(value: LineComment) -> Self {
+        RpcMember::LineComment(value)
+    }
+}
+
+impl From<OptionField> for RpcMember {
+    fn from(value: OptionField) -> Self {
+        RpcMember::Option(value)
+    }
+}
+
+impl TryFrom<&mut TokenStream> for RpcMember {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        let typ = match tokens.peek() {
+            Some(token) => token.typ(),
+            None => return Err(tokens.eof("rpc member")),
+        };
+
+        let member = match typ {
+            Type::Option => Self::from(OptionField::try_from(tokens)?),
+            Type::Semicolon => Self::from(Empty::try_from(tokens)?),
+            Type::LineComment(_) => Self::from(LineComment::try_from(tokens)?),
+            Type::BlockComment(_) => Self::from(BlockComment::try_from(tokens)?),
+            _ => return Err(tokens.unexpected("rpc option, comment or closing brace('}')")),
+        };
+
+        Ok(member)
+    }
+}
+
+impl Display for RpcMember {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let i = level(f);
+
+        match self {
+            Self::BlockComment(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Empty(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::LineComment(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Option(v) => write!(f, "{:indent$}", v, indent = i),
+        }
+    }
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Rpc {
     pub name: String,
-    pub arg: String,
-    pub ret: String,
-    pub options: Option<RpcOption>,
+    pub arg: TypeRef,
+    pub ret: TypeRef,
+    // `None` when the rpc ends with a semicolon instead of a, possibly empty, body
+    pub body: Option<Vec<RpcMember>>,
     pub stream_arg: bool,
     pub stream_ret: bool,
+    pub span: Span,
 }
 
 impl Rpc {
-    pub fn new(name: String, arg: String, ret: String, stream_arg: bool, stream_ret: bool) -> Self {
+    pub fn new(
+        name: String,
+        arg: TypeRef,
+        ret: TypeRef,
+        stream_arg: bool,
+        stream_ret: bool,
+    ) -> Self {
         Rpc {
             name,
             arg,
             ret,
-            options: None,
+            body: None,
             stream_arg,
             stream_ret,
+            span: Span::default(),
         }
     }
 
-    pub fn set_options(&mut self, options: Option<RpcOption>) {
-        self.options = options;
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
     }
-}
-
-impl TryFrom<TokenStream> for Rpc {
-    type Error = ParserError;
-
-    // Parsing backwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("rpc({:?})", tokens);
 
-        tokens.next_eq(Type::Semicolon, "line ending(';')")?;
+    pub fn set_body(&mut self, body: Option<Vec<RpcMember>>) {
+        self.body = body;
+    }
 
-        // Check for RPC options
-        let options = match tokens.peek_eq(Type::RBrace) {
-            true => {
-                let option_tokens = tokens.select_block(Type::RBrace, Type::LBrace);
-                Some(RpcOption::try_from(option_tokens)?)
-            }
-            false => None,
-        };
+    pub fn options(&self) -> impl Iterator<Item = &OptionField> {
+        self.body
+            .iter()
+            .flatten()
+            .filter_map(|member| match member {
+                RpcMember::Option(v) => Some(v),
+                _ => None,
+            })
+    }
 
-        // Handle return value
-        tokens.next_eq(Type::RParen, "rpc closing return parenthesis(')')")?;
-        let ret = tokens.fullident_as_string("rpc return type")?;
+    // Pops a parenthesized, optionally streamed, message type e.g. `(stream Foo)`.
+    fn type_from(
+        tokens: &mut TokenStream,
+        expect_msg: &str,
+    ) -> Result<(TypeRef, bool), ParserError> {
+        tokens.next_eq(
+            Type::LParen,
+            &format!("{expect_msg} opening parenthesis('(')"),
+        )?;
 
-        // Check for streamed return
-        let stream_ret = tokens.peek_eq(Type::Stream);
-        if stream_ret {
-            // Pop one since we used peek to determine the stream_ret
+        // `stream` is a valid type name e.g. `(stream)` as opposed to `(stream Foo)`
+        let stream =
+            tokens.peek_eq(Type::Stream) && !tokens.peeks_eq(&[Type::Stream, Type::RParen]);
+        if stream {
+            // Pop one since we used peek to determine the stream
             tokens.pop();
         }
 
-        tokens.next_eq(Type::LParen, "rpc opening return parenthesis('(')")?;
-        tokens.next_eq(Type::Returns, "rpc returns")?;
+        let typ = TypeRef::type_from(tokens, &format!("{expect_msg} type"))?;
+        tokens.next_eq(
+            Type::RParen,
+            &format!("{expect_msg} closing parenthesis(')')"),
+        )?;
 
-        // Handle argument
-        tokens.next_eq(Type::RParen, "rpc closing argument parenthesis(')')")?;
-        let arg = tokens.fullident_as_string("rpc argument type")?;
+        Ok((typ, stream))
+    }
 
-        // Check for streamed argument
-        let stream_arg = tokens.peek_eq(Type::Stream);
-        if stream_arg {
-            // Pop one since we used peek to determine the stream_arg
-            tokens.pop();
+    // Parses the body statements up until the closing brace which is left for the caller.
+    fn body_from(tokens: &mut TokenStream) -> Vec<RpcMember> {
+        let mut members = vec![];
+
+        while tokens.peek().is_some_and(|t| t.typ() != &Type::RBrace) {
+            let start = tokens.mark();
+
+            match RpcMember::try_from(&mut *tokens) {
+                Ok(member) => members.push(member),
+                Err(e) => tokens.recover(start, e),
+            }
         }
 
-        tokens.next_eq(Type::LParen, "rpc opening argument parenthesis('(')")?;
+        members
+    }
+}
+
+impl TryFrom<&mut TokenStream> for Rpc {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("rpc({:?})", tokens.peek());
+        let start = tokens.mark();
 
+        tokens.next_eq(Type::Rpc, "rpc identifier")?;
         let name = tokens.ident_as_string("rpc name")?;
+        let (arg, stream_arg) = Self::type_from(tokens, "rpc argument")?;
+        tokens.next_eq(Type::Returns, "rpc returns")?;
+        let (ret, stream_ret) = Self::type_from(tokens, "rpc return")?;
 
-        tokens.next_eq(Type::RPC, "rpc identifier")?;
+        // Either a line ending or a body, the body isn't followed by a semicolon
+        let body = match tokens.peek_eq(Type::LBrace) {
+            true => {
+                tokens.next_eq(Type::LBrace, "rpc opening brace('{')")?;
+                let members = Self::body_from(tokens);
+                tokens.next_eq(Type::RBrace, "rpc closing brace('}')")?;
+                Some(members)
+            }
+            false => {
+                tokens.next_eq(Type::Semicolon, "rpc line ending(';') or body('{')")?;
+                None
+            }
+        };
 
         let mut res = Rpc::new(name, arg, ret, stream_arg, stream_ret);
-        res.set_options(options);
+        res.set_body(body);
 
-        return Ok(res);
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
     }
 }
 
 impl Display for Rpc {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let i = level(f);
         indent(f)?;
 
         write!(f, "rpc {} ", self.name)?;
@@ -106,9 +227,158 @@ impl Display for Rpc {
             false => write!(f, "returns ({})", self.ret)?,
         }
 
-        match &self.options {
-            Some(v) => write!(f, " {v};"),
+        match &self.body {
+            Some(members) if members.is_empty() => write!(f, " {{}}"),
+            Some(members) => {
+                writeln!(f, " {{")?;
+                for member in members {
+                    write!(f, "{:indent$}", member, indent = i + 1)?;
+                }
+
+                indent(f)?;
+                write!(f, "}}")
+            }
             None => write!(f, ";"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::token::StringLit;
+    use crate::token_stream::stream;
+
+    use super::*;
+
+    fn signature() -> Vec<Type> {
+        vec![
+            Type::Rpc,
+            Type::Ident("Foo".to_string()),
+            Type::LParen,
+            Type::Ident("Bar".to_string()),
+            Type::RParen,
+            Type::Returns,
+            Type::LParen,
+            Type::Stream,
+            Type::FullIdent("baz.Baz".to_string()),
+            Type::RParen,
+        ]
+    }
+
+    #[test]
+    fn line_ok() {
+        let mut types = signature();
+        types.push(Type::Semicolon);
+
+        let rpc = Rpc::try_from(&mut stream(&types)).unwrap();
+        assert!(rpc.stream_ret);
+        assert_eq!(format!("{rpc}"), "rpc Foo (Bar) returns (stream baz.Baz);");
+    }
+
+    #[test]
+    fn body_ok() {
+        let mut types = signature();
+        types.extend([
+            Type::LBrace,
+            Type::Option,
+            Type::Ident("deprecated".to_string()),
+            Type::Assign,
+            Type::BoolLit(true),
+            Type::Semicolon,
+            Type::Semicolon,
+            Type::Option,
+            Type::LParen,
+            Type::FullIdent("foo.bar".to_string()),
+            Type::RParen,
+            Type::Assign,
+            Type::LBrace,
+            Type::Ident("get".to_string()),
+            Type::Colon,
+            Type::StringLit(StringLit::new(b"/v1".to_vec())),
+            Type::RBrace,
+            Type::Semicolon,
+            Type::RBrace,
+        ]);
+
+        let res = Rpc::try_from(&mut stream(&types));
+        assert!(res.is_ok(), "rpc parse error {:?}", res);
+
+        let rpc = res.unwrap();
+        assert_eq!(rpc.options().count(), 2);
+        assert_eq!(
+            format!("{rpc}"),
+            "rpc Foo (Bar) returns (stream baz.Baz) {\n\
+             \toption deprecated = true;\n\
+             \t;\n\
+             \toption (foo.bar) = { get: \"/v1\" };\n\
+             }"
+        );
+    }
+
+    #[test]
+    fn empty_body_ok() {
+        let mut types = signature();
+        types.extend([Type::LBrace, Type::RBrace]);
+
+        let rpc = Rpc::try_from(&mut stream(&types)).unwrap();
+        assert_eq!(rpc.body, Some(vec![]));
+        assert_eq!(
+            format!("{rpc}"),
+            "rpc Foo (Bar) returns (stream baz.Baz) {}"
+        );
+    }
+
+    #[test]
+    fn comma_separated_err() {
+        let mut types = signature();
+        types.extend([
+            Type::LBrace,
+            Type::Option,
+            Type::Ident("deprecated".to_string()),
+            Type::Assign,
+            Type::BoolLit(true),
+            Type::Comma,
+            Type::RBrace,
+        ]);
+
+        // The broken option is reported and skipped up to the closing brace
+        let mut ts = stream(&types);
+        let rpc = Rpc::try_from(&mut ts).unwrap();
+        assert_eq!(rpc.body, Some(vec![]));
+        assert!(ts.is_empty());
+
+        let diagnostics = ts.take_diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        let error = diagnostics[0].error();
+        assert!(matches!(error, ParserError::UnexpectedToken { found, .. } if found == ","));
+        assert_eq!(error.code(), "E0005");
+    }
+
+    #[test]
+    fn keyword_ident_ok() {
+        let types = [
+            Type::Rpc,
+            Type::Service,
+            Type::LParen,
+            Type::Stream,
+            Type::RParen,
+            Type::Returns,
+            Type::LParen,
+            Type::Stream,
+            Type::Message,
+            Type::RParen,
+            Type::Semicolon,
+        ];
+
+        let res = Rpc::try_from(&mut stream(&types));
+        assert!(res.is_ok(), "rpc parse error {:?}", res);
+
+        let rpc = res.unwrap();
+        assert_eq!((rpc.arg.name(), rpc.stream_arg), ("stream".to_string(), false));
+        assert_eq!((rpc.ret.name(), rpc.stream_ret), ("message".to_string(), true));
+        assert_eq!(
+            format!("{rpc}"),
+            "rpc service (stream) returns (stream message);"
+        );
+    }
+}
@@ -3,8 +3,11 @@ use std::fmt::Display;
 
 use crate::error::ParserError;
 use crate::indent::{indent, level};
+use crate::position::Span;
+use crate::scope::Scope;
 use crate::token::Type;
 use crate::token_stream::TokenStream;
+use crate::types::empty::Empty;
 use crate::types::option_field::OptionField;
 use crate::types::rpc::Rpc;
 use crate::types::comment::{BlockComment, LineComment};
@@ -12,9 +15,16 @@ use crate::types::comment::{BlockComment, LineComment};
 #[derive(Clone, Debug, PartialEq)]
 pub enum ServiceMember {
     BlockComment(BlockComment),
+    Empty(Empty),
     LineComment(LineComment),
     Option(OptionField),
-    RPC(Rpc),
+    Rpc(Rpc),
+}
+
+impl From<Empty> for ServiceMember {
+    fn from(value: Empty) -> Self {
+        Self::Empty(value)
+    }
 }
 
 impl From<BlockComment> for ServiceMember {
@@ -37,13 +47,47 @@ impl From<OptionField> for ServiceMember {
 
 impl From<Rpc> for ServiceMember {
     fn from(value: Rpc) -> Self {
-        ServiceMember::RPC(value)
+        ServiceMember::Rpc(value)
+    }
+}
+
+impl TryFrom<&mut TokenStream> for ServiceMember {
+    type Error = ParserError;
+
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        let typ = match tokens.peek() {
+            Some(token) => token.typ(),
+            None => return Err(tokens.eof("service member")),
+        };
+
+        let member = match typ {
+            Type::Option => Self::from(OptionField::try_from(tokens)?),
+            // Ends at either the semicolon or the closing brace of its body
+            Type::Rpc => Self::from(Rpc::try_from(tokens)?),
+            Type::Semicolon => {
+                // Empty statement e.g. after an rpc body `rpc Foo (Bar) returns (Baz) {};`
+                Self::from(Empty::try_from(tokens)?)
+            }
+            Type::LineComment(_) => Self::from(LineComment::try_from(tokens)?),
+            Type::BlockComment(_) => Self::from(BlockComment::try_from(tokens)?),
+            _ => return Err(tokens.unexpected("service member")),
+        };
+
+        Ok(member)
     }
 }
 
 impl Display for ServiceMember {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self)
+        let i = level(f);
+
+        match self {
+            Self::BlockComment(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Empty(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::LineComment(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Option(v) => write!(f, "{:indent$}", v, indent = i),
+            Self::Rpc(v) => write!(f, "{:indent$}", v, indent = i),
+        }
     }
 }
 
@@ -51,6 +95,7 @@ impl Display for ServiceMember {
 pub struct Service {
     pub name: String,
     pub members: Vec<ServiceMember>,
+    pub span: Span,
 }
 
 impl Service {
@@ -58,74 +103,53 @@ impl Service {
         Service {
             name,
             members: vec![],
+            span: Span::default(),
         }
     }
 
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
     pub fn push(&mut self, member: ServiceMember) {
         self.members.push(member);
     }
 }
 
-impl TryFrom<TokenStream> for Service {
+impl TryFrom<&mut TokenStream> for Service {
     type Error = ParserError;
 
-    // Parsing forwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("service({:?})", &tokens);
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("service({:?})", tokens.peek());
+        let start = tokens.mark();
 
         tokens.next_eq(Type::Service, "service identifier")?;
-        let name = tokens.fullident_as_string("service name")?;
-        tokens.next_eq(Type::LBrace, "serice opening brace('{')")?;
+        let name = tokens.ident_as_string("service name")?;
+        tokens.next_eq(Type::LBrace, "service opening brace('{')")?;
 
         let mut service = Service::new(name.to_string());
+        let mut scope = Scope::new();
 
-        while !tokens.is_empty() {
-            let peek_token = match tokens.peek() {
-                Some(v) => v,
-                None => {
-                    return Err(ParserError::Syntax(
-                        "service option or rpc".to_string(),
-                        "nothing".to_string(),
-                    ))
-                }
-            };
+        while tokens.peek().is_some_and(|t| t.typ() != &Type::RBrace) {
+            let start = tokens.mark();
 
-            let member = match peek_token.typ() {
-                Type::Option => {
-                    let option_tokens = tokens.select_until(Type::Semicolon);
-                    ServiceMember::from(OptionField::try_from(option_tokens)?)
-                }
-                Type::RPC => {
-                    let rpc_tokens = tokens.select_until(Type::Semicolon);
-                    ServiceMember::from(Rpc::try_from(rpc_tokens)?)
-                }
-                Type::Slash => {
-                    if tokens.is_line_comment() {
-                        let line = tokens.select_line_comment()?;
-                        ServiceMember::from(LineComment::from(line))
-                    } else if tokens.is_block_comment() {
-                        let block = tokens.select_block_comment()?;
-                        ServiceMember::from(BlockComment::from(block))
-                    } else {
-                        return Err(ParserError::Syntax(
-                            "service comment".to_string(),
-                            format!("service tokens: {tokens}"),
-                        ));
+            match ServiceMember::try_from(&mut *tokens) {
+                Ok(member) => {
+                    if let ServiceMember::Rpc(v) = &member {
+                        if let Err(e) = scope.define(&v.name, v.span) {
+                            tokens.report(e);
+                        }
                     }
+                    service.push(member)
                 }
-                invalid => {
-                    return Err(ParserError::Syntax(
-                        "service member".to_string(),
-                        format!("{invalid}"),
-                    ))
-                }
-            };
-
-            service.push(member);
+                Err(e) => tokens.recover(start, e),
+            }
         }
 
         tokens.next_eq(Type::RBrace, "service closing brace('}')")?;
 
+        service.set_span(tokens.span_from(start));
+
         Ok(service)
     }
 }
@@ -141,3 +165,71 @@ impl Display for Service {
         writeln!(f, "}}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::token_stream::stream;
+
+    use super::*;
+
+    #[test]
+    fn rpc_body_ok() {
+        let rpc = |name: &str| {
+            vec![
+                Type::Rpc,
+                Type::Ident(name.to_string()),
+                Type::LParen,
+                Type::Ident("Req".to_string()),
+                Type::RParen,
+                Type::Returns,
+                Type::LParen,
+                Type::Ident("Res".to_string()),
+                Type::RParen,
+            ]
+        };
+
+        let mut types = vec![
+            Type::Service,
+            Type::Ident("Foo".to_string()),
+            Type::LBrace,
+        ];
+        types.extend(rpc("Get"));
+        types.extend([Type::LBrace, Type::RBrace]);
+        types.extend(rpc("List"));
+        types.extend([Type::LBrace, Type::RBrace, Type::Semicolon]);
+        types.extend(rpc("Delete"));
+        types.extend([Type::Semicolon, Type::RBrace]);
+
+        let mut ts = stream(&types);
+
+        let res = Service::try_from(&mut ts);
+        assert!(res.is_ok(), "service parse error {:?}", res);
+
+        let service = res.unwrap();
+        // The semicolon after the body of `List` is kept as an empty statement
+        assert_eq!(service.members.len(), 4);
+        assert_eq!(service.members[2], ServiceMember::from(Empty::new()));
+        match &service.members[3] {
+            ServiceMember::Rpc(v) => {
+                assert_eq!(v.name, "Delete");
+                assert_eq!(v.body, None);
+            }
+            invalid => panic!("expected rpc, got {invalid}"),
+        }
+    }
+
+    #[test]
+    fn full_name_err() {
+        let mut ts = stream(&[
+            Type::Service,
+            Type::FullIdent("a.b".to_string()),
+            Type::LBrace,
+            Type::RBrace,
+        ]);
+
+        match Service::try_from(&mut ts) {
+            Err(ParserError::UnexpectedToken { found, .. }) => assert_eq!(found, "a.b"),
+            res => panic!("expected an unexpected token, got {res:?}"),
+        }
+    }
+}
@@ -3,76 +3,109 @@ use std::fmt::Display;
 
 use crate::error::ParserError;
 use crate::indent::indent;
-use crate::token::Type;
+use crate::position::Span;
+use crate::token::{StringLit, Type};
 use crate::token_stream::TokenStream;
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Syntax {
     pub value: String,
+    // The literal as written e.g. `'proto3'`, used when printing
+    pub raw: String,
+    pub span: Span,
 }
 
 impl Syntax {
     pub fn new(value: String) -> Self {
-        Syntax { value }
+        Syntax {
+            raw: StringLit::new(value.as_bytes().to_vec()).raw().to_string(),
+            value,
+            span: Span::default(),
+        }
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn set_raw(&mut self, raw: String) {
+        self.raw = raw;
+    }
+
+    // Files without a syntax statement are proto2
+    pub fn is_proto3(&self) -> bool {
+        self.value == "proto3"
     }
 }
 
-impl TryFrom<TokenStream> for Syntax {
+impl TryFrom<&mut TokenStream> for Syntax {
     type Error = ParserError;
 
-    // Parsing backwards
-    fn try_from(mut tokens: TokenStream) -> Result<Self, Self::Error> {
-        debug!("syntax({:?})", &tokens);
+    fn try_from(tokens: &mut TokenStream) -> Result<Self, Self::Error> {
+        debug!("syntax({:?})", tokens.peek());
+        let start = tokens.mark();
 
-        tokens.next_eq(Type::Semicolon, "syntax line ending(';')")?;
-        let value = tokens.constant_as_string("syntax value")?;
-        tokens.next_eq(Type::Assign, "syntax assignment('=')")?;
         tokens.next_eq(Type::Syntax, "syntax identifier")?;
+        tokens.next_eq(Type::Assign, "syntax assignment('=')")?;
+        let lit = tokens.strlit("syntax value")?;
+        tokens.next_eq(Type::Semicolon, "syntax line ending(';')")?;
 
-        if !value.eq("\"proto2\"") && !value.eq("\"proto3\"") {
-            return Err(ParserError::Syntax(
-                "syntax value to be '\"proto2\"' or '\"proto3\"'".to_string(),
-                format!("{:?}", value),
-            ));
-        }
+        // Either quote style is allowed, the value is kept without quotes and
+        // the literal as written
+        let value = match lit.as_str() {
+            Some(v @ ("proto2" | "proto3")) => v.to_string(),
+            _ => {
+                return Err(ParserError::invalid(
+                    "syntax value to be '\"proto2\"' or '\"proto3\"'",
+                    lit.raw(),
+                ))
+            }
+        };
+
+        let mut res = Self::new(value);
+        res.set_raw(lit.raw().to_string());
+        res.set_span(tokens.span_from(start));
 
-        Ok(Self::new(value))
+        Ok(res)
     }
 }
 
 impl Display for Syntax {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         indent(f)?;
-        write!(f, "syntax = {};", self.value)
+        write!(f, "syntax = {};", self.raw)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::token::Token;
+    use crate::lexer::Lexer;
+    use crate::token_stream::stream;
 
     use super::*;
 
     #[test]
     fn from_ok() {
-        let mut ts = TokenStream::new();
-
-        let _ = &[
+        let mut ts = stream(&[
             Type::Syntax,
             Type::Assign,
-            Type::Constant("\"proto3\"".to_string()),
+            Type::StringLit(StringLit::new(b"proto3".to_vec())),
             Type::Semicolon,
-        ]
-        .iter()
-        .map(|t| Token::from(t.clone()))
-        .collect::<Vec<Token>>()
-        .iter()
-        .for_each(|t| ts.push(t.clone()));
-
-        let res = Syntax::try_from(ts);
+        ]);
+
+        let res = Syntax::try_from(&mut ts);
         assert!(res.is_ok(), "syntax parse error {:?}", res);
 
         let syntax = res.unwrap();
         assert_eq!(format!("{syntax}"), "syntax = \"proto3\";");
     }
+
+    #[test]
+    fn raw_ok() {
+        let tokens = Lexer::new(b"syntax = 'proto3';").tokens().unwrap();
+
+        let syntax = Syntax::try_from(&mut TokenStream::from(tokens)).unwrap();
+        assert!(syntax.is_proto3());
+        assert_eq!(format!("{syntax}"), "syntax = 'proto3';");
+    }
 }
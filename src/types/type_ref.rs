@@ -0,0 +1,245 @@
+// Type references e.g. `int32`, `Foo.Bar` or the fully-qualified `.google.protobuf.Timestamp`
+//
+// Names starting with a dot are absolute and resolved from the root scope, other names are
+// relative and resolved outwards starting from the scope they are used in.
+// https://protobuf.com/docs/language-spec#type-references
+use std::fmt::Display;
+
+use crate::error::ParserError;
+use crate::position::Span;
+use crate::token_stream::TokenStream;
+
+const TYPE_REF_URL: &str = "https://protobuf.com/docs/language-spec#type-references";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scalar {
+    Double,
+    Float,
+    Int32,
+    Int64,
+    UInt32,
+    UInt64,
+    SInt32,
+    SInt64,
+    Fixed32,
+    Fixed64,
+    SFixed32,
+    SFixed64,
+    Bool,
+    String,
+    Bytes,
+}
+
+impl Scalar {
+    // Any integral or string type, used to validate map keys
+    pub fn is_integral_or_string(&self) -> bool {
+        !matches!(self, Self::Double | Self::Float | Self::Bytes)
+    }
+
+    // Numeric and bool types, the ones repeated fields can be packed for
+    pub fn is_packable(&self) -> bool {
+        !matches!(self, Self::String | Self::Bytes)
+    }
+}
+
+impl TryFrom<&str> for Scalar {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "double" => Ok(Self::Double),
+            "float" => Ok(Self::Float),
+            "int32" => Ok(Self::Int32),
+            "int64" => Ok(Self::Int64),
+            "uint32" => Ok(Self::UInt32),
+            "uint64" => Ok(Self::UInt64),
+            "sint32" => Ok(Self::SInt32),
+            "sint64" => Ok(Self::SInt64),
+            "fixed32" => Ok(Self::Fixed32),
+            "fixed64" => Ok(Self::Fixed64),
+            "sfixed32" => Ok(Self::SFixed32),
+            "sfixed64" => Ok(Self::SFixed64),
+            "bool" => Ok(Self::Bool),
+            "string" => Ok(Self::String),
+            "bytes" => Ok(Self::Bytes),
+            invalid => Err(ParserError::expected("scalar type", invalid)),
+        }
+    }
+}
+
+impl Display for Scalar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Double => "double",
+            Self::Float => "float",
+            Self::Int32 => "int32",
+            Self::Int64 => "int64",
+            Self::UInt32 => "uint32",
+            Self::UInt64 => "uint64",
+            Self::SInt32 => "sint32",
+            Self::SInt64 => "sint64",
+            Self::Fixed32 => "fixed32",
+            Self::Fixed64 => "fixed64",
+            Self::SFixed32 => "sfixed32",
+            Self::SFixed64 => "sfixed64",
+            Self::Bool => "bool",
+            Self::String => "string",
+            Self::Bytes => "bytes",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+// What a named type refers to, only known once the name is resolved
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TypeKind {
+    Enum,
+    Message,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeRefKind {
+    Scalar(Scalar),
+    // Message or enum name, the parts exclude the leading dot of absolute names
+    Named { absolute: bool, parts: Vec<String> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeRef {
+    pub kind: TypeRefKind,
+    pub span: Span,
+}
+
+impl TypeRef {
+    pub fn new(kind: TypeRefKind) -> Self {
+        Self {
+            kind,
+            span: Span::default(),
+        }
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = span;
+    }
+
+    pub fn is_scalar(&self) -> bool {
+        matches!(self.kind, TypeRefKind::Scalar(_))
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        matches!(self.kind, TypeRefKind::Named { absolute: true, .. })
+    }
+
+    pub fn as_scalar(&self) -> Option<Scalar> {
+        match self.kind {
+            TypeRefKind::Scalar(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    // Dot separated name without the leading dot e.g. `google.protobuf.Timestamp`
+    pub fn name(&self) -> String {
+        match &self.kind {
+            TypeRefKind::Scalar(v) => format!("{v}"),
+            TypeRefKind::Named { parts, .. } => parts.join("."),
+        }
+    }
+
+    pub(crate) fn type_from(
+        tokens: &mut TokenStream,
+        expect_msg: &str,
+    ) -> Result<Self, ParserError> {
+        let start = tokens.mark();
+        let name = tokens.fullident_as_string(expect_msg)?;
+
+        let mut res = Self::try_from(name.as_str())?;
+        res.set_span(tokens.span_from(start));
+
+        Ok(res)
+    }
+}
+
+impl From<Scalar> for TypeRef {
+    fn from(value: Scalar) -> Self {
+        Self::new(TypeRefKind::Scalar(value))
+    }
+}
+
+impl TryFrom<&str> for TypeRef {
+    type Error = ParserError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (absolute, name) = match value.strip_prefix('.') {
+            Some(v) => (true, v),
+            None => (false, value),
+        };
+
+        // Only relative single part names can be scalars, `.int32` refers to a message or enum
+        if !absolute {
+            if let Ok(v) = Scalar::try_from(name) {
+                return Ok(Self::from(v));
+            }
+        }
+
+        let parts: Vec<String> = name.split('.').map(|part| part.to_string()).collect();
+
+        let is_ident = |part: &String| {
+            part.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_')
+                && part
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        };
+
+        if !parts.iter().all(is_ident) {
+            return Err(ParserError::expected(
+                format!("type name, check {TYPE_REF_URL} for more info"),
+                value,
+            ));
+        }
+
+        Ok(Self::new(TypeRefKind::Named { absolute, parts }))
+    }
+}
+
+impl Display for TypeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            TypeRefKind::Scalar(v) => write!(f, "{v}"),
+            TypeRefKind::Named { absolute: true, .. } => write!(f, ".{}", self.name()),
+            TypeRefKind::Named {
+                absolute: false, ..
+            } => write!(f, "{}", self.name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_ok() {
+        let scalar = TypeRef::try_from("int32").unwrap();
+        assert_eq!(scalar, TypeRef::from(Scalar::Int32));
+
+        let relative = TypeRef::try_from("foo.Bar").unwrap();
+        assert!(!relative.is_absolute() && !relative.is_scalar());
+        assert_eq!(format!("{relative}"), "foo.Bar");
+
+        let absolute = TypeRef::try_from(".google.protobuf.Timestamp").unwrap();
+        assert!(absolute.is_absolute());
+        assert_eq!(absolute.name(), "google.protobuf.Timestamp");
+        assert_eq!(format!("{absolute}"), ".google.protobuf.Timestamp");
+
+        let named = TypeRef::try_from(".int32").unwrap();
+        assert!(!named.is_scalar());
+    }
+
+    #[test]
+    fn invalid_err() {
+        for name in ["", ".", "..foo", "foo.", "foo..Bar", "1foo", "foo-bar"] {
+            assert!(TypeRef::try_from(name).is_err(), "accepted '{name}'");
+        }
+    }
+}
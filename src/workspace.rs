@@ -0,0 +1,346 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use log::debug;
+
+use crate::error::ProtobufError;
+use crate::resolver::{ChainedResolver, DirectoryResolver, SourceResolver};
+use crate::types::import::ImportScope;
+use crate::types::proto::Proto;
+
+// Workspace
+//
+// Files loaded together with everything they import, like `protoc -I`. Import paths are looked up
+// in the include roots and other source resolvers in order, files are keyed by their canonical
+// import path e.g. `./foo/../bar.proto` is `bar.proto`.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dependency {
+    pub path: String,
+    pub scope: Option<ImportScope>,
+}
+
+// Import graph
+//
+// Edges from a file to the files it imports, in import order. Weak imports are allowed to be
+// missing and are only added when found.
+
+#[derive(Debug, Default)]
+pub struct ImportGraph {
+    edges: BTreeMap<String, Vec<Dependency>>,
+}
+
+impl ImportGraph {
+    pub fn imports(&self, path: &str) -> &[Dependency] {
+        self.edges
+            .get(path)
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+    }
+
+    // Files whose definitions `path` can use: its direct imports and, transitively, whatever those
+    // import publicly.
+    pub fn visible(&self, path: &str) -> BTreeSet<String> {
+        let mut res = BTreeSet::new();
+        let mut pending: Vec<&str> = self.imports(path).iter().map(|d| d.path.as_str()).collect();
+
+        while let Some(next) = pending.pop() {
+            if !res.insert(next.to_string()) {
+                continue;
+            }
+
+            for dep in self.imports(next) {
+                if dep.scope == Some(ImportScope::Public) {
+                    pending.push(&dep.path);
+                }
+            }
+        }
+
+        res
+    }
+
+    // Files depending on `path` directly.
+    pub fn importers(&self, path: &str) -> Vec<&str> {
+        self.edges
+            .iter()
+            .filter(|(_, deps)| deps.iter().any(|d| d.path == path))
+            .map(|(file, _)| file.as_str())
+            .collect()
+    }
+}
+
+#[derive(Default)]
+pub struct Workspace {
+    resolver: ChainedResolver,
+    files: BTreeMap<String, Proto>,
+    graph: ImportGraph,
+}
+
+impl Workspace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_root(&mut self, root: impl Into<PathBuf>) {
+        self.resolver.push(DirectoryResolver::new(root));
+    }
+
+    // Resolvers are tried in the order they're pushed, include roots included.
+    pub fn push_resolver(&mut self, resolver: impl SourceResolver + 'static) {
+        self.resolver.push(resolver);
+    }
+
+    pub fn files(&self) -> &BTreeMap<String, Proto> {
+        &self.files
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Proto> {
+        self.files.get(&canonical(path).ok()?)
+    }
+
+    pub fn graph(&self) -> &ImportGraph {
+        &self.graph
+    }
+
+    // Loads the file at import path `path` and every file it imports, files already loaded are
+    // kept. Returns the canonical path of the file.
+    pub fn load(&mut self, path: &str) -> Result<String, ProtobufError> {
+        let path = canonical(path)?;
+        self.load_from(&path, None, &mut vec![])?;
+
+        Ok(path)
+    }
+
+    // `stack` holds the files being loaded, finding one of them again means the imports loop.
+    fn load_from(
+        &mut self,
+        path: &str,
+        importer: Option<&str>,
+        stack: &mut Vec<String>,
+    ) -> Result<(), ProtobufError> {
+        if let Some(pos) = stack.iter().position(|p| p == path) {
+            let mut cycle = stack[pos..].to_vec();
+            cycle.push(path.to_string());
+            return Err(ProtobufError::ImportCycle { cycle });
+        }
+        if self.files.contains_key(path) {
+            return Ok(());
+        }
+
+        debug!("workspace load({path})");
+        let source = match self.resolver.resolve(path)? {
+            Some(v) => v,
+            None => {
+                return Err(ProtobufError::ImportNotFound {
+                    path: path.to_string(),
+                    importer: importer.map(|v| v.to_string()),
+                })
+            }
+        };
+        let proto = crate::parse_file(path, &source)?;
+
+        stack.push(path.to_string());
+
+        let mut deps = vec![];
+        for import in proto.imports() {
+            let dep = Dependency {
+                path: canonical(&import.value)?,
+                scope: import.scope.clone(),
+            };
+
+            match self.load_from(&dep.path, Some(path), stack) {
+                Ok(()) => deps.push(dep),
+                // Only the weak import itself may be missing, not one of the files it imports
+                Err(ProtobufError::ImportNotFound { path: missing, .. })
+                    if dep.scope == Some(ImportScope::Weak) && missing == dep.path =>
+                {
+                    debug!("weak import {} of {path} not found", dep.path);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        stack.pop();
+
+        self.graph.edges.insert(path.to_string(), deps);
+        self.files.insert(path.to_string(), proto);
+
+        Ok(())
+    }
+}
+
+// Normalizes an import path, `.` and `..` components and repeated separators are removed. A `..`
+// going above the include root is an error.
+pub fn canonical(path: &str) -> Result<String, ProtobufError> {
+    let mut parts: Vec<&str> = vec![];
+
+    for part in path.split('/') {
+        match part {
+            "" | "." => (),
+            ".." => {
+                if parts.pop().is_none() {
+                    return Err(ProtobufError::InvalidPath {
+                        path: path.to_string(),
+                    });
+                }
+            }
+            v => parts.push(v),
+        }
+    }
+
+    Ok(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Include root with the given files, removed when dropped
+    struct Root(PathBuf);
+
+    impl Root {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("protobuf-rs-{name}-{}", std::process::id()));
+            for (path, source) in files {
+                let file = dir.join(path);
+                std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+                std::fs::write(file, source).unwrap();
+            }
+            Self(dir)
+        }
+    }
+
+    impl Drop for Root {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn canonical_ok() {
+        assert_eq!(canonical("foo/bar.proto").unwrap(), "foo/bar.proto");
+        assert_eq!(
+            canonical("./foo//baz/../bar.proto").unwrap(),
+            "foo/bar.proto"
+        );
+    }
+
+    #[test]
+    fn canonical_err() {
+        for path in ["../foo.proto", "foo/../../bar.proto", "./.."] {
+            match canonical(path) {
+                Err(e @ ProtobufError::InvalidPath { .. }) => assert_eq!(e.code(), "E0010"),
+                res => panic!("expected an invalid path for {path}, got {res:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn load_ok() {
+        let root = Root::new(
+            "load",
+            &[
+                (
+                    "a.proto",
+                    "import \"b/b.proto\";\nimport weak \"missing.proto\";",
+                ),
+                ("b/b.proto", "import public \"./c.proto\";\nmessage B {}"),
+                ("c.proto", "import \"d.proto\";\nmessage C {}"),
+            ],
+        );
+        let other = Root::new("load-other", &[("d.proto", "message D {}")]);
+
+        let mut workspace = Workspace::new();
+        workspace.push_root(&root.0);
+        workspace.push_root(&other.0);
+
+        assert_eq!(workspace.load("./a.proto").unwrap(), "a.proto");
+        assert_eq!(
+            workspace.files().keys().collect::<Vec<&String>>(),
+            vec!["a.proto", "b/b.proto", "c.proto", "d.proto"]
+        );
+        assert_eq!(workspace.get("b/b.proto").unwrap().name(), "b/b.proto");
+
+        let graph = workspace.graph();
+        assert_eq!(graph.imports("a.proto").len(), 1);
+        assert_eq!(
+            graph
+                .visible("a.proto")
+                .into_iter()
+                .collect::<Vec<String>>(),
+            vec!["b/b.proto", "c.proto"]
+        );
+        assert_eq!(graph.importers("c.proto"), vec!["b/b.proto"]);
+    }
+
+    #[test]
+    fn load_err() {
+        let root = Root::new(
+            "cycle",
+            &[
+                ("a.proto", "import \"b.proto\";"),
+                ("b.proto", "import \"c.proto\";"),
+                ("c.proto", "import \"b.proto\";"),
+                ("d.proto", "import \"e.proto\";"),
+                ("f.proto", "message F { string bar 1; }"),
+                ("g.proto", "import weak \"d.proto\";"),
+            ],
+        );
+
+        let mut workspace = Workspace::new();
+        workspace.push_root(&root.0);
+
+        match workspace.load("a.proto") {
+            Err(ProtobufError::ImportCycle { cycle }) => {
+                assert_eq!(cycle, vec!["b.proto", "c.proto", "b.proto"]);
+            }
+            res => panic!("expected an import cycle, got {res:?}"),
+        }
+
+        match workspace.load("d.proto") {
+            Err(e @ ProtobufError::ImportNotFound { .. }) => {
+                assert_eq!(e.code(), "E0008");
+                assert_eq!(
+                    e.to_string(),
+                    "import 'e.proto' not found in any include root, imported by 'd.proto'"
+                );
+            }
+            res => panic!("expected a missing import, got {res:?}"),
+        }
+
+        // The weak import exists, the file it imports doesn't
+        match workspace.load("missing.proto") {
+            Err(e @ ProtobufError::ImportNotFound { .. }) => assert_eq!(
+                e.to_string(),
+                "import 'missing.proto' not found in any include root"
+            ),
+            res => panic!("expected a missing file, got {res:?}"),
+        }
+
+        match workspace.load("g.proto") {
+            Err(ProtobufError::ImportNotFound { path, importer }) => {
+                assert_eq!(path, "e.proto");
+                assert_eq!(importer.as_deref(), Some("d.proto"));
+            }
+            res => panic!("expected a missing import, got {res:?}"),
+        }
+
+        match workspace.load("f.proto") {
+            Err(e @ ProtobufError::File { .. }) => {
+                assert_eq!(e.code(), "E0005");
+                assert!(e.to_string().starts_with("f.proto: "), "{e}");
+            }
+            res => panic!("expected a syntax error, got {res:?}"),
+        }
+
+        std::fs::write(root.0.join("h.proto"), b"message H {}\n\xff").unwrap();
+        match workspace.load("h.proto") {
+            Err(e @ ProtobufError::File { .. }) => {
+                assert_eq!(e.code(), "E0002");
+                assert_eq!(e.span().map(|s| s.start().line()), Some(2));
+            }
+            res => panic!("expected an invalid encoding, got {res:?}"),
+        }
+    }
+}